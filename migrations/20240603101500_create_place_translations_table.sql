@@ -0,0 +1,7 @@
+CREATE TABLE place_translations (
+  place_id INT NOT NULL REFERENCES places(id) ON DELETE CASCADE,
+  lang VARCHAR(16) NOT NULL,
+  name VARCHAR(100) DEFAULT NULL,
+  description text,
+  PRIMARY KEY (place_id, lang)
+);
@@ -0,0 +1,12 @@
+-- The dumped descriptions were shifted between places, move each one back to
+-- the place it describes.
+UPDATE places
+SET description = source.description
+FROM (VALUES
+  (1, 4), (2, 11), (3, 12), (4, 8), (5, 1),
+  (6, 2), (7, 9), (8, 5), (9, 10), (10, 3),
+  (11, 7), (12, 6), (13, 19), (14, 16), (15, 13),
+  (16, 17), (17, 20), (18, 14), (19, 15), (20, 18)
+) AS moves (place_id, source_id)
+JOIN places source ON source.id = moves.source_id
+WHERE places.id = moves.place_id;
@@ -0,0 +1,11 @@
+CREATE TABLE reviews (
+  id SERIAL PRIMARY KEY,
+  place_id INT NOT NULL REFERENCES places(id) ON DELETE CASCADE,
+  username VARCHAR(128) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+  rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+  text text DEFAULT NULL,
+  hidden BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at TIMESTAMPTZ DEFAULT NULL,
+  UNIQUE (place_id, username)
+);
@@ -0,0 +1,16 @@
+CREATE TABLE favorite_places (
+  username VARCHAR(128) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+  place_id INT NOT NULL REFERENCES places(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (username, place_id)
+);
+
+CREATE TABLE saved_routes (
+  id SERIAL PRIMARY KEY,
+  username VARCHAR(128) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+  name VARCHAR(100) DEFAULT NULL,
+  from_place_id INT NOT NULL REFERENCES places(id) ON DELETE CASCADE,
+  to_place_id INT NOT NULL REFERENCES places(id) ON DELETE CASCADE,
+  departure_time time DEFAULT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,12 @@
+CREATE TABLE route_searches (
+  id SERIAL PRIMARY KEY,
+  username VARCHAR(128) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+  from_place_id INT NOT NULL,
+  to_place_id INT NOT NULL,
+  departure_time time NOT NULL,
+  path_count INT NOT NULL,
+  best_weight INT DEFAULT NULL,
+  searched_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX route_searches_username_idx ON route_searches (username, searched_at DESC);
@@ -0,0 +1,4 @@
+-- The dumps inserted explicit ids, move the sequences past them so new rows
+-- get fresh ids.
+SELECT setval('places_id_seq', (SELECT COALESCE(MAX(id), 1) FROM places));
+SELECT setval('schedules_id_seq', (SELECT COALESCE(MAX(id), 1) FROM schedules));
@@ -0,0 +1,13 @@
+ALTER TABLE places ADD COLUMN gtfs_stop_id VARCHAR(255) UNIQUE DEFAULT NULL;
+
+CREATE TABLE gtfs_routes (
+  route_id VARCHAR(255) PRIMARY KEY,
+  line INT NOT NULL UNIQUE,
+  short_name VARCHAR(100) DEFAULT NULL,
+  long_name VARCHAR(255) DEFAULT NULL,
+  color VARCHAR(6) DEFAULT NULL,
+  type vehicle DEFAULT NULL
+);
+
+ALTER TABLE schedules ADD COLUMN gtfs_trip_id VARCHAR(255) DEFAULT NULL;
+CREATE INDEX schedules_gtfs_trip_id_idx ON schedules (gtfs_trip_id);
@@ -0,0 +1,5 @@
+-- The dumps left every vehicle type empty. Lines 1 and 2 are the 75 km/h rail
+-- lines, the other seeded lines are buses.
+UPDATE schedules
+SET type = CASE WHEN line IN (1, 2) THEN 'TRAIN'::vehicle ELSE 'BUS'::vehicle END
+WHERE type IS NULL AND gtfs_trip_id IS NULL;
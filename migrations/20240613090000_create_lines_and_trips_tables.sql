@@ -0,0 +1,72 @@
+-- Lines and vehicle runs used to be implied by schedule ids: a run was a block
+-- of consecutive ids on one line where each leg starts where the previous one
+-- ended. Make both explicit and derive them from the existing legs.
+CREATE TABLE lines (
+  id INT PRIMARY KEY,
+  short_name VARCHAR(100) DEFAULT NULL,
+  long_name VARCHAR(255) DEFAULT NULL,
+  color VARCHAR(6) DEFAULT NULL,
+  type vehicle DEFAULT NULL,
+  gtfs_route_id VARCHAR(255) UNIQUE DEFAULT NULL
+);
+
+INSERT INTO lines (id, short_name, long_name, color, type, gtfs_route_id)
+SELECT line, short_name, long_name, color, type, route_id FROM gtfs_routes;
+
+INSERT INTO lines (id, type)
+SELECT line, MIN(type) FROM schedules
+WHERE line NOT IN (SELECT id FROM lines)
+GROUP BY line;
+
+CREATE TABLE trips (
+  id SERIAL PRIMARY KEY,
+  line INT NOT NULL REFERENCES lines (id) ON DELETE CASCADE,
+  gtfs_trip_id VARCHAR(255) UNIQUE DEFAULT NULL
+);
+
+CREATE TEMPORARY TABLE derived_legs AS
+WITH breaks AS (
+  SELECT id, line, gtfs_trip_id,
+  CASE WHEN gtfs_trip_id IS NOT NULL
+    THEN gtfs_trip_id IS DISTINCT FROM LAG(gtfs_trip_id) OVER w
+    ELSE COALESCE(
+      LAG(id) OVER w <> id - 1
+      OR LAG(line) OVER w <> line
+      OR LAG(to_place_id) OVER w <> from_place_id
+      OR LAG(arrival_time) OVER w > departure_time
+      OR LAG(gtfs_trip_id) OVER w IS NOT NULL,
+      TRUE
+    )
+  END AS starts_trip
+  FROM schedules
+  WINDOW w AS (ORDER BY id)
+), numbered AS (
+  SELECT id, line, gtfs_trip_id, SUM(starts_trip::int) OVER (ORDER BY id) AS trip_id
+  FROM breaks
+)
+SELECT id, line, gtfs_trip_id, trip_id,
+ROW_NUMBER() OVER (PARTITION BY trip_id ORDER BY id) AS stop_sequence
+FROM numbered;
+
+INSERT INTO trips (id, line, gtfs_trip_id)
+SELECT trip_id, MIN(line), MIN(gtfs_trip_id) FROM derived_legs GROUP BY trip_id;
+SELECT setval('trips_id_seq', (SELECT COALESCE(MAX(id), 1) FROM trips));
+
+ALTER TABLE schedules
+  ADD COLUMN trip_id INT REFERENCES trips (id) ON DELETE CASCADE,
+  ADD COLUMN stop_sequence INT;
+
+UPDATE schedules
+SET trip_id = derived_legs.trip_id, stop_sequence = derived_legs.stop_sequence
+FROM derived_legs
+WHERE schedules.id = derived_legs.id;
+
+ALTER TABLE schedules
+  ALTER COLUMN trip_id SET NOT NULL,
+  ALTER COLUMN stop_sequence SET NOT NULL,
+  ADD CONSTRAINT schedules_line_fkey FOREIGN KEY (line) REFERENCES lines (id),
+  ADD CONSTRAINT schedules_trip_stop_key UNIQUE (trip_id, stop_sequence),
+  DROP COLUMN gtfs_trip_id;
+
+DROP TABLE derived_legs;
+DROP TABLE gtfs_routes;
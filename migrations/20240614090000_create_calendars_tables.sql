@@ -0,0 +1,44 @@
+-- Service calendars say on which days a trip runs. Trips without a calendar
+-- keep running every day.
+CREATE TABLE calendars (
+  id SERIAL PRIMARY KEY,
+  name VARCHAR(100) NOT NULL,
+  monday BOOLEAN NOT NULL DEFAULT TRUE,
+  tuesday BOOLEAN NOT NULL DEFAULT TRUE,
+  wednesday BOOLEAN NOT NULL DEFAULT TRUE,
+  thursday BOOLEAN NOT NULL DEFAULT TRUE,
+  friday BOOLEAN NOT NULL DEFAULT TRUE,
+  saturday BOOLEAN NOT NULL DEFAULT TRUE,
+  sunday BOOLEAN NOT NULL DEFAULT TRUE,
+  start_date DATE NOT NULL,
+  end_date DATE NOT NULL,
+  gtfs_service_id VARCHAR(255) UNIQUE DEFAULT NULL,
+  CHECK (start_date <= end_date)
+);
+
+CREATE TYPE calendar_exception AS ENUM ('ADDED', 'REMOVED');
+
+CREATE TABLE calendar_dates (
+  calendar_id INT NOT NULL REFERENCES calendars (id) ON DELETE CASCADE,
+  date DATE NOT NULL,
+  exception calendar_exception NOT NULL,
+  PRIMARY KEY (calendar_id, date)
+);
+
+ALTER TABLE trips ADD COLUMN calendar_id INT REFERENCES calendars (id) DEFAULT NULL;
+
+-- Added and removed dates win over the weekday pattern.
+CREATE FUNCTION trip_runs_on(trip INT, day DATE) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+  SELECT COALESCE((
+    SELECT COALESCE(
+      (SELECT exception = 'ADDED' FROM calendar_dates
+       WHERE calendar_id = calendars.id AND date = day),
+      day BETWEEN start_date AND end_date
+      AND (ARRAY[monday, tuesday, wednesday, thursday, friday, saturday, sunday])
+        [EXTRACT(ISODOW FROM day)::int]
+    )
+    FROM trips JOIN calendars ON calendars.id = trips.calendar_id
+    WHERE trips.id = trip
+  ), TRUE)
+$$;
@@ -0,0 +1,46 @@
+-- Store leg times as seconds from the start of the service day so trips can
+-- run past midnight (25:10:00 is 01:10 the next morning, as in GTFS).
+ALTER TABLE schedules
+  ALTER COLUMN departure_time TYPE INT USING EXTRACT(EPOCH FROM departure_time)::int,
+  ALTER COLUMN arrival_time TYPE INT USING EXTRACT(EPOCH FROM arrival_time)::int;
+
+-- Legs stored as clock times wrapped back to 00:00 after midnight, move them to
+-- the service day their trip started on.
+DO $$
+DECLARE
+  leg RECORD;
+  current_trip INT;
+  day_offset INT;
+  last_time INT;
+  departure INT;
+BEGIN
+  FOR leg IN
+    SELECT id, trip_id, departure_time, arrival_time FROM schedules
+    WHERE departure_time IS NOT NULL AND arrival_time IS NOT NULL
+    ORDER BY trip_id, stop_sequence
+  LOOP
+    IF leg.trip_id IS DISTINCT FROM current_trip THEN
+      current_trip := leg.trip_id;
+      day_offset := 0;
+      last_time := 0;
+    END IF;
+
+    IF leg.departure_time + day_offset < last_time THEN
+      day_offset := day_offset + 86400;
+    END IF;
+    departure := leg.departure_time + day_offset;
+    IF leg.arrival_time + day_offset < departure THEN
+      day_offset := day_offset + 86400;
+    END IF;
+    last_time := leg.arrival_time + day_offset;
+
+    IF day_offset > 0 THEN
+      UPDATE schedules SET departure_time = departure, arrival_time = last_time
+      WHERE id = leg.id;
+    END IF;
+  END LOOP;
+END $$;
+
+ALTER TABLE schedules
+  ADD CONSTRAINT schedules_service_time_check
+  CHECK (departure_time >= 0 AND arrival_time >= departure_time);
@@ -0,0 +1,17 @@
+-- Fares are counted in satang. A ride pays the base fare of its vehicle once
+-- and a rate per kilometre, a ride taken after a transfer gets the transfer
+-- discount off its base fare.
+CREATE TABLE fare_rules (
+  vehicle vehicle PRIMARY KEY,
+  base_fare INT NOT NULL CHECK (base_fare >= 0),
+  per_km INT NOT NULL CHECK (per_km >= 0),
+  per_zone INT NOT NULL DEFAULT 0 CHECK (per_zone >= 0),
+  transfer_discount INT NOT NULL DEFAULT 0 CHECK (transfer_discount >= 0)
+);
+
+INSERT INTO fare_rules (vehicle, base_fare, per_km, per_zone, transfer_discount) VALUES
+  ('TRAIN', 1600, 150, 0, 500),
+  ('BUS', 800, 50, 0, 400);
+
+-- Optional fare zones, a leg into another zone costs `per_zone` on top.
+ALTER TABLE places ADD COLUMN fare_zone VARCHAR(50) DEFAULT NULL;
@@ -0,0 +1,37 @@
+-- Servers keep the timetable in memory and reload it when one of the tables
+-- it is read from changes, whoever changed it.
+CREATE FUNCTION notify_timetable_changed() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+  PERFORM pg_notify('timetable_changed', TG_TABLE_NAME);
+  RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER schedules_timetable_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON schedules
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
+
+CREATE TRIGGER trips_timetable_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON trips
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
+
+CREATE TRIGGER calendars_timetable_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON calendars
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
+
+CREATE TRIGGER calendar_dates_timetable_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON calendar_dates
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
+
+CREATE TRIGGER fare_rules_timetable_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON fare_rules
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
+
+CREATE TRIGGER lines_timetable_changed
+AFTER UPDATE OF type ON lines
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
+
+CREATE TRIGGER places_timetable_changed
+AFTER UPDATE OF fare_zone ON places
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
@@ -0,0 +1,4 @@
+-- Route search weights used to be clock times read as numbers (08:45 was 845),
+-- they are now seconds of travel. Old values cannot be converted, so drop them.
+ALTER TABLE route_searches RENAME COLUMN best_weight TO best_travel_seconds;
+UPDATE route_searches SET best_travel_seconds = NULL;
@@ -0,0 +1,7 @@
+-- Route search now tells unknown places apart from the timetable in memory,
+-- so it has to be reloaded when places come and go too.
+DROP TRIGGER places_timetable_changed ON places;
+
+CREATE TRIGGER places_timetable_changed
+AFTER INSERT OR UPDATE OF fare_zone OR DELETE OR TRUNCATE ON places
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
@@ -0,0 +1,20 @@
+-- Minimum seconds to change between trips. A rule for the pair of lines wins
+-- over the rule of the place, which wins over the server wide default.
+ALTER TABLE places ADD COLUMN min_transfer_seconds INT DEFAULT NULL
+  CHECK (min_transfer_seconds >= 0);
+
+CREATE TABLE line_transfers (
+  from_line INT NOT NULL REFERENCES lines(id) ON DELETE CASCADE,
+  to_line INT NOT NULL REFERENCES lines(id) ON DELETE CASCADE,
+  min_transfer_seconds INT NOT NULL CHECK (min_transfer_seconds >= 0),
+  PRIMARY KEY (from_line, to_line)
+);
+
+CREATE TRIGGER line_transfers_timetable_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON line_transfers
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
+
+DROP TRIGGER places_timetable_changed ON places;
+CREATE TRIGGER places_timetable_changed
+AFTER UPDATE OF fare_zone, min_transfer_seconds ON places
+FOR EACH STATEMENT EXECUTE FUNCTION notify_timetable_changed();
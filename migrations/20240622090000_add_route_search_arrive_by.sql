@@ -0,0 +1,3 @@
+-- Route searches can ask to arrive by a time, in which case the time recorded
+-- is the one to arrive by rather than the departure time.
+ALTER TABLE route_searches ADD COLUMN arrive_by boolean NOT NULL DEFAULT false;
@@ -0,0 +1,5 @@
+-- GTFS stop_sequence of the stop a leg leaves, kept by the import so that
+-- realtime feeds written against the imported feed find the stop again.
+-- Legs created otherwise have none and their stops are counted from 1.
+ALTER TABLE schedules ADD COLUMN gtfs_stop_sequence INT DEFAULT NULL
+  CHECK (gtfs_stop_sequence >= 0);
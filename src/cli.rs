@@ -0,0 +1,51 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+
+use crate::{
+    config::ServerConfig,
+    gtfs::{
+        export::export_feed,
+        import::{apply_import, plan_import},
+        Feed,
+    },
+    validate::validate_timetable,
+};
+
+const USAGE: &str = "usage:
+  wsc2017_tp17                                   start the server
+  wsc2017_tp17 gtfs-import <feed.zip> [--dry-run]  import a GTFS feed
+  wsc2017_tp17 gtfs-export <feed.zip>              export the network as a GTFS feed
+  wsc2017_tp17 validate                            check the timetable for inconsistencies";
+
+/// Runs a one-off command against the configured database instead of serving.
+pub async fn run(args: &[String], server_config: &ServerConfig) -> Result<()> {
+    let db_pool = &server_config.db_pool.pool;
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+
+    match args.first().map(String::as_str) {
+        Some("gtfs-import") => {
+            let path = args.get(1).ok_or_else(|| anyhow!(USAGE))?;
+            let feed = Feed::read(&fs::read(path)?)?;
+            let report = apply_import(plan_import(&feed), flag("--dry-run"), db_pool).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if report.has_errors() && report.imported_nothing() {
+                return Err(anyhow!("feed not imported, see the report above"));
+            }
+            Ok(())
+        }
+        Some("gtfs-export") => {
+            let path = args.get(1).ok_or_else(|| anyhow!(USAGE))?;
+            fs::write(path, export_feed(db_pool).await?)?;
+            Ok(())
+        }
+        Some("validate") => {
+            let report = validate_timetable(db_pool).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if report.has_errors() {
+                return Err(anyhow!("timetable has errors, see the report above"));
+            }
+            Ok(())
+        }
+        _ => Err(anyhow!(USAGE)),
+    }
+}
@@ -1,13 +1,81 @@
 use crate::routes::{
     auth::{login, logout},
+    calendar::{
+        get::get_calendars,
+        post::post_calendar,
+        slug::{
+            date::{delete_calendar_date, put_calendar_date},
+            delete::delete_calendar,
+            get::find_calendar,
+            put::put_calendar,
+        },
+    },
+    fare::{get::get_fares, quote::quote, slug::put::put_fare},
+    favorite::{
+        get::get_favorite_places,
+        slug::{delete::delete_favorite_place, put::put_favorite_place},
+    },
+    gtfs::{
+        export::export_gtfs,
+        import::{import_gtfs, MAX_FEED_SIZE},
+        realtime::{import_gtfs_realtime, MAX_REALTIME_FEED_SIZE},
+    },
+    line::{
+        get::get_lines,
+        slug::{generate::generate_line_timetable, get::find_line, timetable::get_line_timetable},
+    },
     place::{
         get::get_places,
-        slug::{delete::delete_place, get::find_place},
+        slug::{
+            board::{get_arrivals, get_departures},
+            delete::delete_place,
+            get::find_place,
+            translation::{
+                delete::delete_place_translation, get::get_place_translations,
+                put::put_place_translation,
+            },
+        },
+    },
+    realtime::{delete::delete_realtime, get::get_realtime, post::post_realtime, RealtimeStore},
+    review::{
+        get::get_place_reviews,
+        post::post_place_review,
+        slug::{delete::delete_review, put::put_review, visibility::put_review_visibility},
+    },
+    route::{
+        history::{
+            delete::delete_route_history, get::get_route_history, popular::get_popular_routes,
+        },
+        search::{
+            shortest_paths,
+            timetable::{listen_for_changes, TimetableCache},
+        },
+    },
+    saved_route::{
+        get::get_saved_routes,
+        post::post_saved_route,
+        slug::{delete::delete_saved_route, put::put_saved_route},
+    },
+    schedule::{
+        get::get_schedules,
+        post::post_schedule,
+        slug::{delete::delete_schedule, get::find_schedule, put::put_schedule},
+        status::put_schedule_status,
+        validation::get_schedule_validation,
+    },
+    transfer::{
+        get::get_transfers,
+        slug::{
+            line::{delete_line_transfer, put_line_transfer},
+            place::put_place_transfer,
+        },
+        DEFAULT_MIN_TRANSFER_SECONDS, MAX_TRANSFER_SECONDS,
     },
-    route::search::shortest_paths,
+    trip::slug::calendar::put_trip_calendar,
     DatabasePool,
 };
 use actix_web::{
+    rt::task::JoinHandle,
     web::{self, Data, ServiceConfig},
     HttpResponse,
 };
@@ -17,6 +85,10 @@ use std::{collections::HashMap, fs, io};
 #[derive(Clone)]
 pub struct ServerConfig {
     pub db_pool: Data<DatabasePool>,
+    /// Delays and cancellations, kept in memory for as long as the server runs.
+    pub realtime: Data<RealtimeStore>,
+    /// Timetable searches run on, reloaded whenever it changes.
+    pub timetable_cache: Data<TimetableCache>,
     pub env: HashMap<String, String>,
 }
 
@@ -24,6 +96,20 @@ impl ServerConfig {
     pub async fn new() -> Self {
         let env = read_env().expect("Reading of env failed");
         let db_url = env.get("DATABASE_URL").expect("env not found");
+        let min_transfer_seconds = env
+            .get("MIN_TRANSFER_SECONDS")
+            .map(|seconds| {
+                let seconds = seconds
+                    .parse()
+                    .expect("MIN_TRANSFER_SECONDS is not a number");
+                assert!(
+                    (0..=MAX_TRANSFER_SECONDS).contains(&seconds),
+                    "MIN_TRANSFER_SECONDS must be between 0 and {}",
+                    MAX_TRANSFER_SECONDS
+                );
+                seconds
+            })
+            .unwrap_or(DEFAULT_MIN_TRANSFER_SECONDS);
         let db_pool: Data<DatabasePool> = web::Data::new(DatabasePool {
             pool: PgPoolOptions::new()
                 .connect(db_url)
@@ -31,11 +117,27 @@ impl ServerConfig {
                 .expect("Connection to database not failed"),
         });
 
-        Self { db_pool, env }
+        Self {
+            db_pool,
+            realtime: Data::new(RealtimeStore::default()),
+            timetable_cache: Data::new(TimetableCache::new(min_transfer_seconds)),
+            env,
+        }
+    }
+
+    /// Keeps `timetable_cache` up to date in the background, see
+    /// `listen_for_changes`. The task has to be stopped before the runtime
+    /// shuts down, the listener cannot be dropped without it.
+    pub fn listen_for_changes(&self) -> JoinHandle<()> {
+        let timetable_cache = self.timetable_cache.clone();
+        let db_pool = self.db_pool.pool.clone();
+        actix_web::rt::spawn(async move { listen_for_changes(&timetable_cache, &db_pool).await })
     }
 
     pub fn config(&self, cfg: &mut ServiceConfig) {
         cfg.app_data(self.db_pool.clone());
+        cfg.app_data(self.realtime.clone());
+        cfg.app_data(self.timetable_cache.clone());
         cfg.route("/", web::get().to(HttpResponse::Ok)).service(
             web::scope("/v1")
                 .service(
@@ -48,12 +150,135 @@ impl ServerConfig {
                         .get(find_place)
                         .delete(delete_place),
                 )
+                .route("/place/{id}/departures", web::get().to(get_departures))
+                .route("/place/{id}/arrivals", web::get().to(get_arrivals))
+                .route(
+                    "/place/{id}/translation",
+                    web::get().to(get_place_translations),
+                )
+                .service(
+                    web::resource("/place/{id}/translation/{lang}")
+                        .put(put_place_translation)
+                        .delete(delete_place_translation),
+                )
+                .service(
+                    web::resource("/place/{id}/review")
+                        .get(get_place_reviews)
+                        .post(post_place_review),
+                )
                 .route("/place", web::get().to(get_places))
                 .service(
-                    web::scope("/route").service(
-                        web::resource("/search/{from_place_id}/{to_place_id}/{departure_time}")
-                            .get(shortest_paths),
-                    ),
+                    web::scope("/review/{review_id}")
+                        .service(web::resource("").put(put_review).delete(delete_review))
+                        .route("/visibility", web::put().to(put_review_visibility)),
+                )
+                .service(
+                    web::scope("/favorite/place")
+                        .route("", web::get().to(get_favorite_places))
+                        .service(
+                            web::resource("/{id}")
+                                .put(put_favorite_place)
+                                .delete(delete_favorite_place),
+                        ),
+                )
+                .service(
+                    web::scope("/saved-route")
+                        .service(
+                            web::resource("")
+                                .get(get_saved_routes)
+                                .post(post_saved_route),
+                        )
+                        .service(
+                            web::resource("/{saved_route_id}")
+                                .put(put_saved_route)
+                                .delete(delete_saved_route),
+                        ),
+                )
+                .service(
+                    web::scope("/line")
+                        .service(web::resource("").get(get_lines))
+                        .service(web::resource("/{line_id}").get(find_line))
+                        .service(web::resource("/{line_id}/timetable").get(get_line_timetable))
+                        .service(
+                            web::resource("/{line_id}/generate").post(generate_line_timetable),
+                        ),
+                )
+                .service(
+                    web::scope("/fare")
+                        .service(web::resource("/rules").get(get_fares))
+                        .service(web::resource("/rules/{vehicle}").put(put_fare))
+                        .service(web::resource("/quote").post(quote)),
+                )
+                .service(
+                    web::scope("/transfer")
+                        .service(web::resource("").get(get_transfers))
+                        .service(web::resource("/place/{place_id}").put(put_place_transfer))
+                        .service(
+                            web::resource("/line/{from_line}/{to_line}")
+                                .put(put_line_transfer)
+                                .delete(delete_line_transfer),
+                        ),
+                )
+                .service(
+                    web::scope("/calendar")
+                        .service(web::resource("").get(get_calendars).post(post_calendar))
+                        .service(
+                            web::resource("/{calendar_id}")
+                                .get(find_calendar)
+                                .put(put_calendar)
+                                .delete(delete_calendar),
+                        )
+                        .service(
+                            web::resource("/{calendar_id}/date/{date}")
+                                .put(put_calendar_date)
+                                .delete(delete_calendar_date),
+                        ),
+                )
+                .route("/trip/{trip_id}/calendar", web::put().to(put_trip_calendar))
+                .service(
+                    web::scope("/schedule")
+                        .service(web::resource("").get(get_schedules).post(post_schedule))
+                        .service(web::resource("/status").put(put_schedule_status))
+                        .service(web::resource("/validation").get(get_schedule_validation))
+                        .service(
+                            web::resource("/{schedule_id}")
+                                .get(find_schedule)
+                                .put(put_schedule)
+                                .delete(delete_schedule),
+                        ),
+                )
+                .service(
+                    web::scope("/gtfs")
+                        .service(web::resource("/export").get(export_gtfs))
+                        .service(
+                            web::resource("/import")
+                                .app_data(web::PayloadConfig::new(MAX_FEED_SIZE))
+                                .post(import_gtfs),
+                        )
+                        .service(
+                            web::resource("/realtime")
+                                .app_data(web::PayloadConfig::new(MAX_REALTIME_FEED_SIZE))
+                                .post(import_gtfs_realtime),
+                        ),
+                )
+                .service(
+                    web::resource("/realtime")
+                        .get(get_realtime)
+                        .post(post_realtime)
+                        .delete(delete_realtime),
+                )
+                .service(
+                    web::scope("/route")
+                        .service(
+                            web::resource("/search/{from_place_id}/{to_place_id}/{departure_time}")
+                                .get(shortest_paths),
+                        )
+                        .service(
+                            web::resource("/history")
+                                .get(get_route_history)
+                                .delete(delete_route_history),
+                        )
+                        .route("/history/popular", web::get().to(get_popular_routes)),
                 ),
         );
     }
@@ -0,0 +1,375 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::routes::{begin_snapshot, service_time::ServiceTime, CalendarException, Vehicle};
+
+const AGENCY_ID: &str = "TP17";
+const AGENCY_NAME: &str = "Tuk Tuk Hop";
+const AGENCY_URL: &str = "https://www.tuktukhop.com";
+const AGENCY_TIMEZONE: &str = "Asia/Bangkok";
+/// Service of the trips without a calendar.
+const DAILY_SERVICE_ID: &str = "DAILY";
+
+struct ExportLeg {
+    trip_id: i32,
+    from_place_id: i32,
+    to_place_id: i32,
+    departure_time: ServiceTime,
+    arrival_time: ServiceTime,
+    distance: Option<i32>,
+    gtfs_stop_sequence: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct AgencyRow<'a> {
+    agency_id: &'a str,
+    agency_name: &'a str,
+    agency_url: &'a str,
+    agency_timezone: &'a str,
+}
+
+#[derive(Serialize)]
+struct StopRow {
+    stop_id: String,
+    stop_name: String,
+    stop_lat: f64,
+    stop_lon: f64,
+    zone_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RouteRow {
+    route_id: String,
+    agency_id: &'static str,
+    route_short_name: String,
+    route_long_name: String,
+    route_type: u32,
+    route_color: String,
+}
+
+#[derive(Serialize)]
+struct TripRow {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+}
+
+#[derive(Serialize)]
+struct StopTimeRow {
+    trip_id: String,
+    arrival_time: String,
+    departure_time: String,
+    stop_id: String,
+    stop_sequence: i32,
+    shape_dist_traveled: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct CalendarRow {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: String,
+    end_date: String,
+}
+
+#[derive(Serialize)]
+struct CalendarDateRow {
+    service_id: String,
+    date: String,
+    exception_type: u8,
+}
+
+fn to_csv<T: Serialize>(rows: impl IntoIterator<Item = T>) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer.serialize(row)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
+/// Stop id of a place in exported feeds.
+pub fn stop_id(gtfs_stop_id: &Option<String>, place_id: i32) -> String {
+    gtfs_stop_id
+        .clone()
+        .unwrap_or_else(|| format!("P{}", place_id))
+}
+
+/// `stop_sequence` of every stop of a trip in exported feeds, given the
+/// `gtfs_stop_sequence` of its legs in travel order. The ones kept by the import
+/// are used when every leg has one, the stops are counted from 1 otherwise. No
+/// leg leaves the last stop, it comes right after the last leg's.
+pub fn stop_sequences(gtfs_stop_sequences: &[Option<i32>]) -> Vec<i32> {
+    let leg_stops = gtfs_stop_sequences
+        .iter()
+        .copied()
+        .collect::<Option<Vec<i32>>>()
+        .unwrap_or_else(|| (1..).take(gtfs_stop_sequences.len()).collect());
+    let last_stop = leg_stops.last().map(|last| last.saturating_add(1));
+
+    leg_stops.into_iter().chain(last_stop).collect()
+}
+
+/// Exported id of the row `id` refers to, an error when it is missing.
+fn exported_id<'a>(ids: &'a HashMap<i32, String>, id: i32, table: &str) -> Result<&'a String> {
+    ids.get(&id)
+        .ok_or_else(|| anyhow!("{} {} not found", table, id))
+}
+
+/// Builds a GTFS zip out of `places`, `lines`, `trips` and their `schedules`,
+/// all read from the same snapshot.
+pub async fn export_feed(db_pool: &Pool<Postgres>) -> Result<Vec<u8>> {
+    let mut tx = begin_snapshot(db_pool).await?;
+    let places = sqlx::query!(
+        "SELECT id, name, latitude as \"latitude!\", longitude as \"longitude!\", gtfs_stop_id,
+         fare_zone
+         FROM places
+         WHERE latitude IS NOT NULL AND longitude IS NOT NULL
+         ORDER BY id"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let lines = sqlx::query!(
+        "SELECT id, short_name, long_name, color, type as \"vehicle_type: Vehicle\", gtfs_route_id
+         FROM lines
+         ORDER BY id"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let trips = sqlx::query!("SELECT id, line, gtfs_trip_id, calendar_id FROM trips ORDER BY id")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let calendars = sqlx::query!(
+        "SELECT id, monday, tuesday, wednesday, thursday, friday, saturday, sunday,
+         start_date, end_date, gtfs_service_id
+         FROM calendars ORDER BY id"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let calendar_dates = sqlx::query!(
+        "SELECT calendar_id, date, exception as \"exception: CalendarException\"
+         FROM calendar_dates ORDER BY calendar_id, date"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let legs = sqlx::query_as!(
+        ExportLeg,
+        "SELECT trip_id, from_place_id, to_place_id, departure_time as \"departure_time!: ServiceTime\",
+         arrival_time as \"arrival_time!: ServiceTime\", distance, gtfs_stop_sequence
+         FROM schedules
+         WHERE departure_time IS NOT NULL AND arrival_time IS NOT NULL
+         AND from_place_id IN (SELECT id FROM places WHERE latitude IS NOT NULL AND longitude IS NOT NULL)
+         AND to_place_id IN (SELECT id FROM places WHERE latitude IS NOT NULL AND longitude IS NOT NULL)
+         ORDER BY trip_id, stop_sequence"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let stop_ids: HashMap<i32, String> = places
+        .iter()
+        .map(|place| (place.id, stop_id(&place.gtfs_stop_id, place.id)))
+        .collect();
+    let route_ids: HashMap<i32, String> = lines
+        .iter()
+        .map(|line| {
+            let route_id = line
+                .gtfs_route_id
+                .clone()
+                .unwrap_or_else(|| format!("L{}", line.id));
+            (line.id, route_id)
+        })
+        .collect();
+
+    let service_ids: HashMap<i32, String> = calendars
+        .iter()
+        .map(|calendar| {
+            let service_id = calendar
+                .gtfs_service_id
+                .clone()
+                .unwrap_or_else(|| format!("C{}", calendar.id));
+            (calendar.id, service_id)
+        })
+        .collect();
+
+    let mut trip_legs: HashMap<i32, Vec<ExportLeg>> = HashMap::new();
+    for leg in legs {
+        trip_legs.entry(leg.trip_id).or_default().push(leg);
+    }
+
+    let mut trip_rows = Vec::new();
+    let mut stop_time_rows = Vec::new();
+    for trip in &trips {
+        let legs = match trip_legs.get(&trip.id) {
+            Some(legs) => legs,
+            None => continue,
+        };
+        let first = &legs[0];
+        let trip_id = trip
+            .gtfs_trip_id
+            .clone()
+            .unwrap_or_else(|| format!("T{}", trip.id));
+        trip_rows.push(TripRow {
+            route_id: exported_id(&route_ids, trip.line, "line")?.clone(),
+            service_id: match trip.calendar_id {
+                Some(calendar_id) => exported_id(&service_ids, calendar_id, "calendar")?.clone(),
+                None => DAILY_SERVICE_ID.to_owned(),
+            },
+            trip_id: trip_id.clone(),
+        });
+
+        let sequences = stop_sequences(
+            &legs
+                .iter()
+                .map(|leg| leg.gtfs_stop_sequence)
+                .collect::<Vec<_>>(),
+        );
+        let mut traveled = Some(0);
+        stop_time_rows.push(StopTimeRow {
+            trip_id: trip_id.clone(),
+            arrival_time: first.departure_time.to_string(),
+            departure_time: first.departure_time.to_string(),
+            stop_id: exported_id(&stop_ids, first.from_place_id, "place")?.clone(),
+            stop_sequence: sequences[0],
+            shape_dist_traveled: traveled,
+        });
+        for (i, leg) in legs.iter().enumerate() {
+            traveled = traveled
+                .zip(leg.distance)
+                .map(|(sum, distance)| sum + distance);
+            let departure = legs
+                .get(i + 1)
+                .map_or(leg.arrival_time, |next| next.departure_time);
+            stop_time_rows.push(StopTimeRow {
+                trip_id: trip_id.clone(),
+                arrival_time: leg.arrival_time.to_string(),
+                departure_time: departure.to_string(),
+                stop_id: exported_id(&stop_ids, leg.to_place_id, "place")?.clone(),
+                stop_sequence: sequences[i + 1],
+                shape_dist_traveled: traveled,
+            });
+        }
+    }
+
+    let today = Utc::now().date_naive();
+    let mut calendar_rows = calendars
+        .iter()
+        .map(|calendar| {
+            Ok(CalendarRow {
+                service_id: exported_id(&service_ids, calendar.id, "calendar")?.clone(),
+                monday: calendar.monday.into(),
+                tuesday: calendar.tuesday.into(),
+                wednesday: calendar.wednesday.into(),
+                thursday: calendar.thursday.into(),
+                friday: calendar.friday.into(),
+                saturday: calendar.saturday.into(),
+                sunday: calendar.sunday.into(),
+                start_date: calendar.start_date.format("%Y%m%d").to_string(),
+                end_date: calendar.end_date.format("%Y%m%d").to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if trips.iter().any(|trip| trip.calendar_id.is_none()) {
+        calendar_rows.push(CalendarRow {
+            service_id: DAILY_SERVICE_ID.to_owned(),
+            monday: 1,
+            tuesday: 1,
+            wednesday: 1,
+            thursday: 1,
+            friday: 1,
+            saturday: 1,
+            sunday: 1,
+            start_date: today.format("%Y%m%d").to_string(),
+            end_date: (today + Duration::days(365)).format("%Y%m%d").to_string(),
+        });
+    }
+
+    let calendar_date_rows = calendar_dates
+        .iter()
+        .map(|date| {
+            Ok(CalendarDateRow {
+                service_id: exported_id(&service_ids, date.calendar_id, "calendar")?.clone(),
+                date: date.date.format("%Y%m%d").to_string(),
+                exception_type: match date.exception {
+                    CalendarException::ADDED => 1,
+                    CalendarException::REMOVED => 2,
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let files = [
+        (
+            "agency.txt",
+            to_csv([AgencyRow {
+                agency_id: AGENCY_ID,
+                agency_name: AGENCY_NAME,
+                agency_url: AGENCY_URL,
+                agency_timezone: AGENCY_TIMEZONE,
+            }])?,
+        ),
+        (
+            "stops.txt",
+            to_csv(places.iter().map(|place| {
+                StopRow {
+                    stop_id: stop_ids[&place.id].clone(),
+                    stop_name: place
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| stop_ids[&place.id].clone()),
+                    stop_lat: place.latitude,
+                    stop_lon: place.longitude,
+                    zone_id: place.fare_zone.clone(),
+                }
+            }))?,
+        ),
+        (
+            "routes.txt",
+            to_csv(lines.iter().map(|line| {
+                RouteRow {
+                    route_id: route_ids[&line.id].clone(),
+                    agency_id: AGENCY_ID,
+                    route_short_name: line
+                        .short_name
+                        .clone()
+                        .unwrap_or_else(|| line.id.to_string()),
+                    route_long_name: line.long_name.clone().unwrap_or_default(),
+                    route_type: match line.vehicle_type {
+                        Some(Vehicle::TRAIN) => 2,
+                        Some(Vehicle::BUS) | None => 3,
+                    },
+                    route_color: line.color.clone().unwrap_or_default(),
+                }
+            }))?,
+        ),
+        ("trips.txt", to_csv(trip_rows)?),
+        ("stop_times.txt", to_csv(stop_time_rows)?),
+        ("calendar.txt", to_csv(calendar_rows)?),
+        ("calendar_dates.txt", to_csv(calendar_date_rows)?),
+    ];
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        zip.start_file(name, FileOptions::default())?;
+        zip.write_all(&content)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
@@ -0,0 +1,842 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use super::{haversine_distance, vehicle_from_route_type, Feed, Issue, IssueKind, Severity, Table};
+use crate::routes::{service_time::ServiceTime, CalendarException, Vehicle};
+
+const STOPS: &str = "stops.txt";
+const ROUTES: &str = "routes.txt";
+const TRIPS: &str = "trips.txt";
+const STOP_TIMES: &str = "stop_times.txt";
+const CALENDAR: &str = "calendar.txt";
+const CALENDAR_DATES: &str = "calendar_dates.txt";
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Columns read from each supported file, the required ones first.
+const COLUMNS: [(&str, &[&str], &[&str]); 4] = [
+    (
+        STOPS,
+        &["stop_id", "stop_name", "stop_lat", "stop_lon"],
+        &["location_type"],
+    ),
+    (
+        ROUTES,
+        &["route_id", "route_type"],
+        &["route_short_name", "route_long_name", "route_color"],
+    ),
+    (TRIPS, &["route_id", "trip_id"], &["service_id"]),
+    (
+        STOP_TIMES,
+        &[
+            "trip_id",
+            "arrival_time",
+            "departure_time",
+            "stop_id",
+            "stop_sequence",
+        ],
+        &[],
+    ),
+];
+
+/// Columns read from each supported file a feed may leave out.
+const OPTIONAL_COLUMNS: [(&str, &[&str], &[&str]); 2] = [
+    (
+        CALENDAR,
+        &[
+            "service_id",
+            "monday",
+            "tuesday",
+            "wednesday",
+            "thursday",
+            "friday",
+            "saturday",
+            "sunday",
+            "start_date",
+            "end_date",
+        ],
+        &[],
+    ),
+    (
+        CALENDAR_DATES,
+        &["service_id", "date", "exception_type"],
+        &[],
+    ),
+];
+
+#[derive(Debug)]
+pub struct StopPlan {
+    pub stop_id: String,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub fare_zone: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RoutePlan {
+    pub route_id: String,
+    pub short_name: Option<String>,
+    pub long_name: Option<String>,
+    pub color: Option<String>,
+    pub vehicle: Vehicle,
+}
+
+#[derive(Debug)]
+pub struct LegPlan {
+    /// GTFS `stop_sequence` of the stop the leg leaves.
+    pub stop_sequence: i32,
+    pub from_stop_id: String,
+    pub to_stop_id: String,
+    pub departure_time: ServiceTime,
+    pub arrival_time: ServiceTime,
+    pub distance: Option<i32>,
+    pub speed: Option<i32>,
+}
+
+/// Days a service runs, from calendar.txt, calendar_dates.txt or both.
+#[derive(Debug)]
+pub struct CalendarPlan {
+    pub service_id: String,
+    /// Monday first.
+    pub weekdays: [bool; 7],
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub dates: Vec<(NaiveDate, CalendarException)>,
+}
+
+#[derive(Debug)]
+pub struct TripPlan {
+    pub trip_id: String,
+    pub route_id: String,
+    /// Runs every day when `None`.
+    pub service_id: Option<String>,
+    pub legs: Vec<LegPlan>,
+}
+
+/// What a feed would change, worked out without touching the database.
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    pub stops: Vec<StopPlan>,
+    pub routes: Vec<RoutePlan>,
+    pub calendars: Vec<CalendarPlan>,
+    pub trips: Vec<TripPlan>,
+    pub issues: Vec<Issue>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub places_created: usize,
+    pub places_updated: usize,
+    pub lines_created: usize,
+    pub calendars_imported: usize,
+    pub trips_imported: usize,
+    pub schedules_created: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl ImportReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error)
+    }
+
+    /// Whether nothing of the feed made it in, as when its errors leave
+    /// nothing valid to import.
+    pub fn imported_nothing(&self) -> bool {
+        self.places_created
+            + self.places_updated
+            + self.lines_created
+            + self.calendars_imported
+            + self.trips_imported
+            == 0
+    }
+}
+
+pub fn plan_import(feed: &Feed) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+
+    let mut names: Vec<&String> = feed.files.keys().collect();
+    names.sort();
+    for name in names {
+        if !COLUMNS
+            .iter()
+            .chain(&OPTIONAL_COLUMNS)
+            .any(|(file, _, _)| file == name)
+        {
+            plan.issues.push(Issue::warning(
+                IssueKind::UnsupportedFile,
+                name,
+                None,
+                format!("{} is not imported", name),
+            ));
+        }
+    }
+
+    let files = COLUMNS
+        .iter()
+        .map(|columns| (columns, true))
+        .chain(OPTIONAL_COLUMNS.iter().map(|columns| (columns, false)));
+    for (&(file, required, optional), required_file) in files {
+        let table = match feed.files.get(file) {
+            // an empty optional file is as good as a missing one
+            None if !required_file => continue,
+            Some(table) if !required_file && table.rows.is_empty() => continue,
+            Some(table) => table,
+            None => {
+                plan.issues.push(Issue::error(
+                    IssueKind::MissingFile,
+                    file,
+                    None,
+                    format!("{} is required", file),
+                ));
+                continue;
+            }
+        };
+
+        for column in required {
+            if table.column(column).is_none() {
+                plan.issues.push(Issue::error(
+                    IssueKind::MissingField,
+                    file,
+                    Some(1),
+                    format!("{} is required", column),
+                ));
+            }
+        }
+        for header in &table.headers {
+            if !required.contains(&header.as_str()) && !optional.contains(&header.as_str()) {
+                plan.issues.push(Issue::warning(
+                    IssueKind::UnsupportedField,
+                    file,
+                    Some(1),
+                    format!("{} is ignored", header),
+                ));
+            }
+        }
+    }
+
+    // nothing can be imported reliably without every required file and column
+    if plan
+        .issues
+        .iter()
+        .any(|issue| issue.severity == Severity::Error)
+    {
+        return plan;
+    }
+
+    plan_stops(&feed.files[STOPS], &mut plan);
+    plan_routes(&feed.files[ROUTES], &mut plan);
+    plan_calendars(
+        feed.files.get(CALENDAR),
+        feed.files.get(CALENDAR_DATES),
+        &mut plan,
+    );
+    plan_trips(&feed.files[TRIPS], &feed.files[STOP_TIMES], &mut plan);
+    plan
+}
+
+fn field<'a>(table: &Table, row: &'a StringRecord, column: &str) -> Option<&'a str> {
+    table
+        .column(column)
+        .and_then(|i| row.get(i))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn line(row: &StringRecord) -> Option<u64> {
+    row.position().map(|position| position.line())
+}
+
+fn plan_stops(table: &Table, plan: &mut ImportPlan) {
+    for row in &table.rows {
+        let location_type = field(table, row, "location_type").unwrap_or("0");
+        if location_type != "0" {
+            plan.issues.push(Issue::warning(
+                IssueKind::UnsupportedValue,
+                STOPS,
+                line(row),
+                format!("location_type {} is not imported", location_type),
+            ));
+            continue;
+        }
+
+        let stop_id = field(table, row, "stop_id");
+        let coordinates = field(table, row, "stop_lat")
+            .and_then(|lat| lat.parse::<f64>().ok())
+            .zip(field(table, row, "stop_lon").and_then(|lon| lon.parse::<f64>().ok()));
+        match (stop_id, coordinates) {
+            (Some(stop_id), Some((latitude, longitude))) => plan.stops.push(StopPlan {
+                stop_id: stop_id.to_owned(),
+                name: field(table, row, "stop_name")
+                    .unwrap_or(stop_id)
+                    .chars()
+                    .take(100)
+                    .collect(),
+                latitude,
+                longitude,
+                fare_zone: field(table, row, "zone_id").map(|zone| zone.chars().take(50).collect()),
+            }),
+            _ => plan.issues.push(Issue::error(
+                IssueKind::InvalidValue,
+                STOPS,
+                line(row),
+                "stop needs a stop_id, stop_lat and stop_lon".to_owned(),
+            )),
+        }
+    }
+}
+
+fn plan_routes(table: &Table, plan: &mut ImportPlan) {
+    for row in &table.rows {
+        let route_id = match field(table, row, "route_id") {
+            Some(route_id) => route_id,
+            None => {
+                plan.issues.push(Issue::error(
+                    IssueKind::InvalidValue,
+                    ROUTES,
+                    line(row),
+                    "route_id is empty".to_owned(),
+                ));
+                continue;
+            }
+        };
+
+        let route_type = field(table, row, "route_type").unwrap_or_default();
+        let vehicle = match route_type.parse().ok().and_then(vehicle_from_route_type) {
+            Some(vehicle) => vehicle,
+            None => {
+                plan.issues.push(Issue::warning(
+                    IssueKind::UnsupportedValue,
+                    ROUTES,
+                    line(row),
+                    format!(
+                        "route {} has unsupported route_type {}",
+                        route_id, route_type
+                    ),
+                ));
+                continue;
+            }
+        };
+
+        plan.routes.push(RoutePlan {
+            route_id: route_id.to_owned(),
+            short_name: field(table, row, "route_short_name")
+                .map(|name| name.chars().take(100).collect()),
+            long_name: field(table, row, "route_long_name")
+                .map(|name| name.chars().take(255).collect()),
+            color: field(table, row, "route_color")
+                .filter(|color| color.len() == 6)
+                .map(str::to_uppercase),
+            vehicle,
+        });
+    }
+}
+
+fn date(table: &Table, row: &StringRecord, column: &str) -> Option<NaiveDate> {
+    field(table, row, column).and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+}
+
+fn plan_calendars(calendar: Option<&Table>, calendar_dates: Option<&Table>, plan: &mut ImportPlan) {
+    let mut calendars: Vec<CalendarPlan> = Vec::new();
+    for (table, row) in calendar
+        .into_iter()
+        .flat_map(|table| table.rows.iter().map(move |row| (table, row)))
+    {
+        let weekdays = WEEKDAYS.map(|day| match field(table, row, day) {
+            Some("0") => Some(false),
+            Some("1") => Some(true),
+            _ => None,
+        });
+        let parsed = field(table, row, "service_id")
+            .zip(date(table, row, "start_date"))
+            .zip(date(table, row, "end_date"))
+            .filter(|((_, start_date), end_date)| start_date <= end_date)
+            .zip(
+                weekdays
+                    .into_iter()
+                    .collect::<Option<Vec<bool>>>()
+                    .and_then(|weekdays| <[bool; 7]>::try_from(weekdays).ok()),
+            );
+        match parsed {
+            Some((((service_id, start_date), end_date), weekdays)) => {
+                calendars.push(CalendarPlan {
+                    service_id: service_id.to_owned(),
+                    weekdays,
+                    start_date,
+                    end_date,
+                    dates: Vec::new(),
+                })
+            }
+            None => plan.issues.push(Issue::error(
+                IssueKind::InvalidValue,
+                CALENDAR,
+                line(row),
+                "calendar needs a service_id, weekdays of 0 or 1 and a start_date no later \
+                 than its end_date"
+                    .to_owned(),
+            )),
+        }
+    }
+
+    for (table, row) in calendar_dates
+        .into_iter()
+        .flat_map(|table| table.rows.iter().map(move |row| (table, row)))
+    {
+        let exception = match field(table, row, "exception_type") {
+            Some("1") => Some(CalendarException::ADDED),
+            Some("2") => Some(CalendarException::REMOVED),
+            _ => None,
+        };
+        let parsed = field(table, row, "service_id")
+            .zip(date(table, row, "date"))
+            .zip(exception);
+        let ((service_id, date), exception) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                plan.issues.push(Issue::error(
+                    IssueKind::InvalidValue,
+                    CALENDAR_DATES,
+                    line(row),
+                    "calendar date needs a service_id, date and exception_type of 1 or 2"
+                        .to_owned(),
+                ));
+                continue;
+            }
+        };
+
+        // services only listed here run on the dates added to them
+        match calendars
+            .iter_mut()
+            .find(|calendar| calendar.service_id == service_id)
+        {
+            Some(calendar) => {
+                calendar.start_date = calendar.start_date.min(date);
+                calendar.end_date = calendar.end_date.max(date);
+                calendar.dates.push((date, exception));
+            }
+            None => calendars.push(CalendarPlan {
+                service_id: service_id.to_owned(),
+                weekdays: [false; 7],
+                start_date: date,
+                end_date: date,
+                dates: vec![(date, exception)],
+            }),
+        }
+    }
+
+    plan.calendars = calendars;
+}
+
+struct StopTime {
+    line: Option<u64>,
+    stop_sequence: i32,
+    stop_id: String,
+    arrival: ServiceTime,
+    departure: ServiceTime,
+}
+
+fn plan_trips(trips: &Table, stop_times: &Table, plan: &mut ImportPlan) {
+    let stops: HashMap<&str, &StopPlan> = plan
+        .stops
+        .iter()
+        .map(|stop| (stop.stop_id.as_str(), stop))
+        .collect();
+    let routes: HashSet<&str> = plan
+        .routes
+        .iter()
+        .map(|route| route.route_id.as_str())
+        .collect();
+
+    let services: HashSet<&str> = plan
+        .calendars
+        .iter()
+        .map(|calendar| calendar.service_id.as_str())
+        .collect();
+
+    let mut trip_routes: Vec<(String, String, Option<String>)> = Vec::new();
+    for row in &trips.rows {
+        let service_id = field(trips, row, "service_id");
+        match (field(trips, row, "trip_id"), field(trips, row, "route_id")) {
+            (Some(trip_id), Some(_)) if service_id.is_some_and(|id| !services.contains(id)) => {
+                plan.issues.push(Issue::error(
+                    IssueKind::UnknownService,
+                    TRIPS,
+                    line(row),
+                    format!(
+                        "trip {} uses unknown or skipped service {}",
+                        trip_id,
+                        service_id.unwrap_or_default()
+                    ),
+                ))
+            }
+            (Some(trip_id), Some(route_id)) if routes.contains(route_id) => trip_routes.push((
+                trip_id.to_owned(),
+                route_id.to_owned(),
+                service_id.map(str::to_owned),
+            )),
+            (Some(trip_id), Some(route_id)) => plan.issues.push(Issue::error(
+                IssueKind::UnknownRoute,
+                TRIPS,
+                line(row),
+                format!(
+                    "trip {} uses unknown or skipped route {}",
+                    trip_id, route_id
+                ),
+            )),
+            _ => plan.issues.push(Issue::error(
+                IssueKind::InvalidValue,
+                TRIPS,
+                line(row),
+                "trip needs a trip_id and route_id".to_owned(),
+            )),
+        }
+    }
+    let known_trips: HashSet<&str> = trips
+        .rows
+        .iter()
+        .filter_map(|row| field(trips, row, "trip_id"))
+        .collect();
+
+    let mut trip_stop_times: HashMap<&str, Vec<StopTime>> = HashMap::new();
+    let mut skipped_trips: HashSet<&str> = HashSet::new();
+    for row in &stop_times.rows {
+        let trip_id = field(stop_times, row, "trip_id").unwrap_or_default();
+        if !known_trips.contains(trip_id) {
+            plan.issues.push(Issue::error(
+                IssueKind::UnknownTrip,
+                STOP_TIMES,
+                line(row),
+                format!("unknown trip {}", trip_id),
+            ));
+            continue;
+        }
+
+        let stop_id = field(stop_times, row, "stop_id").unwrap_or_default();
+        if !stops.contains_key(stop_id) {
+            plan.issues.push(Issue::error(
+                IssueKind::UnknownStop,
+                STOP_TIMES,
+                line(row),
+                format!("trip {} stops at unknown stop {}", trip_id, stop_id),
+            ));
+            skipped_trips.insert(trip_id);
+            continue;
+        }
+
+        let arrival = field(stop_times, row, "arrival_time");
+        let departure = field(stop_times, row, "departure_time");
+        let (arrival, departure) = match (arrival.or(departure), departure.or(arrival)) {
+            (Some(arrival), Some(departure)) => (arrival, departure),
+            _ => {
+                plan.issues.push(Issue::error(
+                    IssueKind::UnsupportedValue,
+                    STOP_TIMES,
+                    line(row),
+                    format!("trip {} has a stop without times", trip_id),
+                ));
+                skipped_trips.insert(trip_id);
+                continue;
+            }
+        };
+
+        let parsed = ServiceTime::from_str(arrival)
+            .ok()
+            .zip(ServiceTime::from_str(departure).ok())
+            .zip(
+                field(stop_times, row, "stop_sequence")
+                    .and_then(|seq| seq.parse::<u32>().ok())
+                    .and_then(|seq| i32::try_from(seq).ok()),
+            );
+        let ((arrival, departure), stop_sequence) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                plan.issues.push(Issue::error(
+                    IssueKind::InvalidValue,
+                    STOP_TIMES,
+                    line(row),
+                    format!("trip {} has an invalid time or stop_sequence", trip_id),
+                ));
+                skipped_trips.insert(trip_id);
+                continue;
+            }
+        };
+
+        trip_stop_times.entry(trip_id).or_default().push(StopTime {
+            line: line(row),
+            stop_sequence,
+            stop_id: stop_id.to_owned(),
+            arrival,
+            departure,
+        });
+    }
+
+    for (trip_id, route_id, service_id) in trip_routes {
+        if skipped_trips.contains(trip_id.as_str()) {
+            continue;
+        }
+        let mut times = match trip_stop_times.remove(trip_id.as_str()) {
+            Some(times) if times.len() >= 2 => times,
+            _ => {
+                plan.issues.push(Issue::warning(
+                    IssueKind::InvalidValue,
+                    STOP_TIMES,
+                    None,
+                    format!("trip {} has less than two stops", trip_id),
+                ));
+                continue;
+            }
+        };
+        times.sort_by_key(|time| time.stop_sequence);
+
+        let non_monotonic = times
+            .iter()
+            .find(|time| time.arrival > time.departure)
+            .or(times
+                .windows(2)
+                .find(|pair| pair[1].arrival < pair[0].departure)
+                .map(|pair| &pair[1]));
+        if let Some(time) = non_monotonic {
+            plan.issues.push(Issue::error(
+                IssueKind::NonMonotonicTime,
+                STOP_TIMES,
+                time.line,
+                format!(
+                    "trip {} goes back in time at stop_sequence {}",
+                    trip_id, time.stop_sequence
+                ),
+            ));
+            continue;
+        }
+
+        let legs = times
+            .windows(2)
+            .map(|pair| {
+                let (from, to) = (
+                    stops[pair[0].stop_id.as_str()],
+                    stops[pair[1].stop_id.as_str()],
+                );
+                let distance = haversine_distance(
+                    (from.latitude, from.longitude),
+                    (to.latitude, to.longitude),
+                );
+                let duration = pair[1].arrival.seconds() - pair[0].departure.seconds();
+                LegPlan {
+                    stop_sequence: pair[0].stop_sequence,
+                    from_stop_id: from.stop_id.clone(),
+                    to_stop_id: to.stop_id.clone(),
+                    departure_time: pair[0].departure,
+                    arrival_time: pair[1].arrival,
+                    distance: Some(distance.round() as i32),
+                    speed: (duration > 0)
+                        .then(|| (distance / duration as f64 * 3.6).round() as i32),
+                }
+            })
+            .collect();
+
+        plan.trips.push(TripPlan {
+            trip_id,
+            route_id,
+            service_id,
+            legs,
+        });
+    }
+}
+
+/// Writes the plan in a single transaction. A dry run goes through the same
+/// statements and rolls back, so the report matches what a real run would do.
+pub async fn apply_import(
+    plan: ImportPlan,
+    dry_run: bool,
+    db_pool: &Pool<Postgres>,
+) -> Result<ImportReport> {
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut tx = db_pool.begin().await?;
+
+    let mut place_ids: HashMap<String, i32> = HashMap::new();
+    for stop in &plan.stops {
+        let record = sqlx::query!(
+            "INSERT INTO places (name, latitude, longitude, gtfs_stop_id, fare_zone)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (gtfs_stop_id)
+             DO UPDATE SET name = EXCLUDED.name, latitude = EXCLUDED.latitude,
+             longitude = EXCLUDED.longitude, fare_zone = EXCLUDED.fare_zone
+             RETURNING id, (xmax = 0) as \"inserted!\"",
+            stop.name,
+            stop.latitude,
+            stop.longitude,
+            stop.stop_id,
+            stop.fare_zone
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if record.inserted {
+            report.places_created += 1;
+        } else {
+            report.places_updated += 1;
+        }
+        place_ids.insert(stop.stop_id.clone(), record.id);
+    }
+
+    let mut lines: HashMap<String, (i32, Vehicle)> = HashMap::new();
+    for route in &plan.routes {
+        let existing = sqlx::query_scalar!(
+            "SELECT id FROM lines WHERE gtfs_route_id = $1",
+            route.route_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let line = match existing {
+            Some(line) => line,
+            None => {
+                report.lines_created += 1;
+                // line numbers are also picked by hand, so there is no sequence
+                // to draw from. Keep other writers out until the import is in,
+                // or they could take the same free number.
+                sqlx::query!("LOCK TABLE lines IN SHARE ROW EXCLUSIVE MODE")
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query_scalar!("SELECT COALESCE(MAX(id), 0) + 1 as \"line!\" FROM lines")
+                    .fetch_one(&mut *tx)
+                    .await?
+            }
+        };
+
+        sqlx::query!(
+            "INSERT INTO lines (id, short_name, long_name, color, type, gtfs_route_id)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (id)
+             DO UPDATE SET short_name = EXCLUDED.short_name, long_name = EXCLUDED.long_name,
+             color = EXCLUDED.color, type = EXCLUDED.type",
+            line,
+            route.short_name,
+            route.long_name,
+            route.color,
+            route.vehicle as Vehicle,
+            route.route_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        lines.insert(route.route_id.clone(), (line, route.vehicle));
+    }
+
+    let mut calendar_ids: HashMap<String, i32> = HashMap::new();
+    for calendar in &plan.calendars {
+        let [monday, tuesday, wednesday, thursday, friday, saturday, sunday] = calendar.weekdays;
+        let calendar_id = sqlx::query_scalar!(
+            "INSERT INTO calendars (name, monday, tuesday, wednesday, thursday, friday, saturday,
+             sunday, start_date, end_date, gtfs_service_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             ON CONFLICT (gtfs_service_id)
+             DO UPDATE SET monday = EXCLUDED.monday, tuesday = EXCLUDED.tuesday,
+             wednesday = EXCLUDED.wednesday, thursday = EXCLUDED.thursday,
+             friday = EXCLUDED.friday, saturday = EXCLUDED.saturday, sunday = EXCLUDED.sunday,
+             start_date = EXCLUDED.start_date, end_date = EXCLUDED.end_date
+             RETURNING id",
+            calendar.service_id.chars().take(100).collect::<String>(),
+            monday,
+            tuesday,
+            wednesday,
+            thursday,
+            friday,
+            saturday,
+            sunday,
+            calendar.start_date,
+            calendar.end_date,
+            calendar.service_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "DELETE FROM calendar_dates WHERE calendar_id = $1",
+            calendar_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for (date, exception) in &calendar.dates {
+            sqlx::query!(
+                "INSERT INTO calendar_dates (calendar_id, date, exception) VALUES ($1, $2, $3)
+                 ON CONFLICT (calendar_id, date) DO UPDATE SET exception = EXCLUDED.exception",
+                calendar_id,
+                date,
+                *exception as CalendarException
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        report.calendars_imported += 1;
+        calendar_ids.insert(calendar.service_id.clone(), calendar_id);
+    }
+
+    for trip in &plan.trips {
+        let (line, vehicle) = lines[&trip.route_id];
+        // dropping the old run takes its legs with it
+        sqlx::query!("DELETE FROM trips WHERE gtfs_trip_id = $1", trip.trip_id)
+            .execute(&mut *tx)
+            .await?;
+        let trip_id = sqlx::query_scalar!(
+            "INSERT INTO trips (line, gtfs_trip_id, calendar_id) VALUES ($1, $2, $3) RETURNING id",
+            line,
+            trip.trip_id,
+            trip.service_id
+                .as_ref()
+                .and_then(|service_id| calendar_ids.get(service_id))
+                .copied()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (stop_sequence, leg) in (1..).zip(&trip.legs) {
+            sqlx::query!(
+                "INSERT INTO schedules (line, from_place_id, to_place_id, type, departure_time,
+                 arrival_time, distance, speed, trip_id, stop_sequence, gtfs_stop_sequence)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+                line,
+                place_ids[&leg.from_stop_id],
+                place_ids[&leg.to_stop_id],
+                vehicle as Vehicle,
+                leg.departure_time.seconds(),
+                leg.arrival_time.seconds(),
+                leg.distance,
+                leg.speed,
+                trip_id,
+                stop_sequence,
+                leg.stop_sequence
+            )
+            .execute(&mut *tx)
+            .await?;
+            report.schedules_created += 1;
+        }
+        report.trips_imported += 1;
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+
+    report.issues = plan.issues;
+    Ok(report)
+}
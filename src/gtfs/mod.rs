@@ -0,0 +1,138 @@
+pub mod export;
+pub mod import;
+pub mod realtime;
+
+use anyhow::Result;
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use crate::routes::Vehicle;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    MissingFile,
+    MissingField,
+    InvalidValue,
+    UnknownStop,
+    UnknownRoute,
+    UnknownTrip,
+    UnknownService,
+    NonMonotonicTime,
+    UnsupportedFile,
+    UnsupportedField,
+    UnsupportedValue,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub file: String,
+    /// Line in the file, counting the header as line 1.
+    pub line: Option<u64>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn error(kind: IssueKind, file: &str, line: Option<u64>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            kind,
+            file: file.to_owned(),
+            line,
+            message,
+        }
+    }
+
+    pub fn warning(kind: IssueKind, file: &str, line: Option<u64>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            kind,
+            file: file.to_owned(),
+            line,
+            message,
+        }
+    }
+}
+
+/// A CSV file of the feed with its header row split off.
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<StringRecord>,
+}
+
+impl Table {
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.headers.iter().position(|header| header == name)
+    }
+}
+
+/// Every `.txt` file found at the root of a GTFS zip.
+pub struct Feed {
+    pub files: HashMap<String, Table>,
+}
+
+impl Feed {
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+        let mut files = HashMap::new();
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let name = match file.name().rsplit('/').next() {
+                Some(name) if name.ends_with(".txt") => name.to_owned(),
+                _ => continue,
+            };
+
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            files.insert(name, read_table(&buf)?);
+        }
+
+        Ok(Self { files })
+    }
+}
+
+fn read_table(buf: &[u8]) -> Result<Table> {
+    let buf = buf.strip_prefix("\u{feff}".as_bytes()).unwrap_or(buf);
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(buf);
+    let headers = reader
+        .headers()?
+        .iter()
+        .map(|header| header.trim().to_owned())
+        .collect();
+    let rows = reader.records().collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Table { headers, rows })
+}
+
+pub fn vehicle_from_route_type(route_type: u32) -> Option<Vehicle> {
+    match route_type {
+        // tram, subway, rail, cable tram, funicular, monorail
+        0 | 1 | 2 | 5 | 7 | 12 => Some(Vehicle::TRAIN),
+        // bus, trolleybus
+        3 | 11 => Some(Vehicle::BUS),
+        _ => None,
+    }
+}
+
+/// Great-circle distance in meters.
+pub fn haversine_distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
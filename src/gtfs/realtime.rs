@@ -0,0 +1,318 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDate, TimeZone};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+
+use super::export::{stop_id, stop_sequences};
+use crate::routes::realtime::{StopUpdate, TripUpdate};
+
+// Subset of gtfs-realtime.proto needed to read trip updates, field numbers
+// follow the specification.
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FeedMessage {
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<FeedHeader>,
+    #[prost(message, repeated, tag = "2")]
+    pub entity: Vec<FeedEntity>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FeedHeader {
+    #[prost(string, optional, tag = "1")]
+    pub gtfs_realtime_version: Option<String>,
+    #[prost(uint64, optional, tag = "3")]
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FeedEntity {
+    #[prost(string, optional, tag = "1")]
+    pub id: Option<String>,
+    #[prost(bool, optional, tag = "2")]
+    pub is_deleted: Option<bool>,
+    #[prost(message, optional, tag = "3")]
+    pub trip_update: Option<FeedTripUpdate>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FeedTripUpdate {
+    #[prost(message, optional, tag = "1")]
+    pub trip: Option<TripDescriptor>,
+    #[prost(message, repeated, tag = "2")]
+    pub stop_time_update: Vec<StopTimeUpdate>,
+    #[prost(int32, optional, tag = "5")]
+    pub delay: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TripDescriptor {
+    #[prost(string, optional, tag = "1")]
+    pub trip_id: Option<String>,
+    /// `YYYYMMDD` service day of the trip.
+    #[prost(string, optional, tag = "3")]
+    pub start_date: Option<String>,
+    #[prost(enumeration = "TripScheduleRelationship", optional, tag = "4")]
+    pub schedule_relationship: Option<i32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum TripScheduleRelationship {
+    Scheduled = 0,
+    Added = 1,
+    Unscheduled = 2,
+    Canceled = 3,
+    Replacement = 5,
+    Duplicated = 6,
+    Deleted = 7,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct StopTimeUpdate {
+    #[prost(uint32, optional, tag = "1")]
+    pub stop_sequence: Option<u32>,
+    #[prost(message, optional, tag = "2")]
+    pub arrival: Option<StopTimeEvent>,
+    #[prost(message, optional, tag = "3")]
+    pub departure: Option<StopTimeEvent>,
+    #[prost(string, optional, tag = "4")]
+    pub stop_id: Option<String>,
+    #[prost(enumeration = "StopScheduleRelationship", optional, tag = "5")]
+    pub schedule_relationship: Option<i32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum StopScheduleRelationship {
+    Scheduled = 0,
+    Skipped = 1,
+    NoData = 2,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct StopTimeEvent {
+    #[prost(int32, optional, tag = "1")]
+    pub delay: Option<i32>,
+    /// Predicted time in seconds since the epoch.
+    #[prost(int64, optional, tag = "2")]
+    pub time: Option<i64>,
+}
+
+/// Feed entities left out of an ingestion and why.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SkippedEntity {
+    pub entity_id: String,
+    pub reason: String,
+}
+
+/// A stop of a trip as the feed knows it, with its scheduled times.
+struct TripStop {
+    stop_sequence: i32,
+    stop_id: String,
+    arrival_time: Option<i32>,
+    departure_time: Option<i32>,
+}
+
+/// Trip updates matched against the static trips. Trips are found by their
+/// exported GTFS id, stops by `stop_id` or else by their exported `stop_sequence`.
+pub async fn read_trip_updates(
+    bytes: &[u8],
+    db_pool: &Pool<Postgres>,
+) -> Result<(Vec<TripUpdate>, Vec<SkippedEntity>)> {
+    let feed = FeedMessage::decode(bytes)?;
+
+    let trip_ids: HashMap<String, i32> = sqlx::query!("SELECT id, gtfs_trip_id FROM trips")
+        .fetch_all(db_pool)
+        .await?
+        .into_iter()
+        .map(|trip| {
+            let gtfs_trip_id = trip.gtfs_trip_id.unwrap_or_else(|| format!("T{}", trip.id));
+            (gtfs_trip_id, trip.id)
+        })
+        .collect();
+
+    // entities of known trips, read once the stops of all of them are in
+    let mut matched = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, entity) in feed.entity.into_iter().enumerate() {
+        let entity_id = entity.id.clone().unwrap_or_else(|| i.to_string());
+        let mut skip = |reason: &str| {
+            skipped.push(SkippedEntity {
+                entity_id: entity_id.clone(),
+                reason: reason.to_owned(),
+            })
+        };
+
+        let trip_update = match entity.trip_update {
+            Some(trip_update) if !entity.is_deleted.unwrap_or(false) => trip_update,
+            _ => continue,
+        };
+        let trip = trip_update.trip.clone().unwrap_or_default();
+        let trip_id = match trip.trip_id.as_ref().and_then(|id| trip_ids.get(id)) {
+            Some(&trip_id) => trip_id,
+            None => {
+                skip("unknown trip");
+                continue;
+            }
+        };
+        let start_date = match trip.start_date.as_deref() {
+            Some(date) => match NaiveDate::parse_from_str(date, "%Y%m%d") {
+                Ok(date) => date,
+                Err(_) => {
+                    skip("invalid start_date");
+                    continue;
+                }
+            },
+            None => Local::now().date_naive(),
+        };
+        matched.push((entity_id, trip_id, trip, trip_update, start_date));
+    }
+
+    let matched_trip_ids: Vec<i32> = matched.iter().map(|(_, trip_id, ..)| *trip_id).collect();
+    let trip_stops = get_trip_stops(&matched_trip_ids, db_pool).await?;
+    let mut updates = Vec::new();
+    for (entity_id, trip_id, trip, trip_update, start_date) in matched {
+        let stops = trip_stops.get(&trip_id).map_or(&[][..], Vec::as_slice);
+        match to_trip_update(trip_id, &trip, &trip_update, stops, start_date) {
+            Ok(update) => updates.push(update),
+            Err(reason) => skipped.push(SkippedEntity { entity_id, reason }),
+        }
+    }
+
+    Ok((updates, skipped))
+}
+
+/// Stops of each trip in travel order, the trips without legs left out.
+async fn get_trip_stops(
+    trip_ids: &[i32],
+    db_pool: &Pool<Postgres>,
+) -> Result<HashMap<i32, Vec<TripStop>>> {
+    let legs = sqlx::query!(
+        "SELECT trip_id, from_place.id as from_id, from_place.gtfs_stop_id as from_stop_id,
+         to_place.id as to_id, to_place.gtfs_stop_id as to_stop_id,
+         departure_time, arrival_time, gtfs_stop_sequence
+         FROM schedules
+         JOIN places from_place ON from_place.id = schedules.from_place_id
+         JOIN places to_place ON to_place.id = schedules.to_place_id
+         WHERE trip_id = ANY($1)
+         ORDER BY trip_id, stop_sequence",
+        trip_ids
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut trip_legs: HashMap<i32, Vec<_>> = HashMap::new();
+    for leg in legs {
+        trip_legs.entry(leg.trip_id).or_default().push(leg);
+    }
+
+    let mut trip_stops = HashMap::new();
+    for (trip_id, legs) in trip_legs {
+        let sequences = stop_sequences(
+            &legs
+                .iter()
+                .map(|leg| leg.gtfs_stop_sequence)
+                .collect::<Vec<_>>(),
+        );
+        let mut stops: Vec<TripStop> = Vec::new();
+        for (leg, &stop_sequence) in legs.into_iter().zip(sequences.iter().skip(1)) {
+            match stops.last_mut() {
+                Some(stop) => stop.departure_time = leg.departure_time,
+                None => stops.push(TripStop {
+                    stop_sequence: sequences[0],
+                    stop_id: stop_id(&leg.from_stop_id, leg.from_id),
+                    arrival_time: None,
+                    departure_time: leg.departure_time,
+                }),
+            }
+            stops.push(TripStop {
+                stop_sequence,
+                stop_id: stop_id(&leg.to_stop_id, leg.to_id),
+                arrival_time: leg.arrival_time,
+                departure_time: None,
+            });
+        }
+        trip_stops.insert(trip_id, stops);
+    }
+
+    Ok(trip_stops)
+}
+
+fn to_trip_update(
+    trip_id: i32,
+    trip: &TripDescriptor,
+    trip_update: &FeedTripUpdate,
+    stops: &[TripStop],
+    start_date: NaiveDate,
+) -> Result<TripUpdate, String> {
+    // GTFS-Realtime times are counted from noon minus 12 hours of the service day
+    let service_day_start = Local
+        .from_local_datetime(&start_date.and_hms_opt(12, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|noon| noon.timestamp() - 12 * 60 * 60)
+        .ok_or("invalid start_date")?;
+    // a predicted time too far off for a delay fails the whole update
+    let delay_of = |event: &Option<StopTimeEvent>, scheduled: Option<i32>| match event {
+        Some(StopTimeEvent {
+            delay: Some(delay), ..
+        }) => Ok(Some(*delay)),
+        Some(StopTimeEvent {
+            time: Some(time), ..
+        }) => scheduled
+            .map(|scheduled| {
+                time.checked_sub(service_day_start)
+                    .and_then(|predicted| predicted.checked_sub(i64::from(scheduled)))
+                    .and_then(|delay| i32::try_from(delay).ok())
+                    .ok_or("time out of range")
+            })
+            .transpose(),
+        _ => Ok(None),
+    };
+
+    let mut stop_updates = Vec::new();
+    let mut searched_from = 0;
+    for update in &trip_update.stop_time_update {
+        // a trip may call twice at a stop, its next call is the one updated
+        let position = match (update.stop_id.as_ref(), update.stop_sequence) {
+            (Some(stop_id), _) => stops[searched_from..]
+                .iter()
+                .position(|stop| &stop.stop_id == stop_id)
+                .map(|found| searched_from + found)
+                .ok_or("unknown stop_id")?,
+            (None, Some(stop_sequence)) => stops
+                .iter()
+                .position(|stop| i64::from(stop.stop_sequence) == i64::from(stop_sequence))
+                .ok_or("unknown stop_sequence")?,
+            (None, None) => return Err("stop update without stop".to_owned()),
+        };
+        let stop = &stops[position];
+        searched_from = position + 1;
+
+        stop_updates.push(StopUpdate {
+            stop_sequence: stop.stop_sequence,
+            arrival_delay: delay_of(&update.arrival, stop.arrival_time.or(stop.departure_time))?,
+            departure_delay: delay_of(
+                &update.departure,
+                stop.departure_time.or(stop.arrival_time),
+            )?,
+            skipped: update.schedule_relationship == Some(StopScheduleRelationship::Skipped as i32),
+        });
+    }
+
+    Ok(TripUpdate {
+        trip_id,
+        service_date: Some(start_date),
+        cancelled: matches!(
+            trip.schedule_relationship
+                .map(TripScheduleRelationship::try_from),
+            Some(Ok(
+                TripScheduleRelationship::Canceled | TripScheduleRelationship::Deleted
+            ))
+        ),
+        delay: trip_update.delay,
+        stops: stop_updates,
+    })
+}
@@ -1,2 +1,5 @@
+pub mod cli;
 pub mod config;
+pub mod gtfs;
 pub mod routes;
+pub mod validate;
@@ -1,11 +1,16 @@
 use actix_web::{App, HttpServer};
 use anyhow::Result;
-use std::net::SocketAddr;
-use wsc2017_tp17::config::ServerConfig;
+use std::{env, net::SocketAddr};
+use wsc2017_tp17::{cli, config::ServerConfig};
 
 #[actix_web::main]
 async fn main() -> Result<()> {
     let server_config = ServerConfig::new().await;
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args, &server_config).await;
+    }
+
     let host = server_config
         .env
         .get("HOST")
@@ -16,6 +21,8 @@ async fn main() -> Result<()> {
         .expect("server port from env var not found");
     let server_addr = format!("{}:{}", host, port);
 
+    let listener = server_config.listen_for_changes();
+
     HttpServer::new(move || {
         let server_config = server_config.clone();
         App::new().configure(move |cfg| server_config.config(cfg))
@@ -24,5 +31,8 @@ async fn main() -> Result<()> {
     .run()
     .await?;
 
+    listener.abort();
+    let _ = listener.await;
+
     Ok(())
 }
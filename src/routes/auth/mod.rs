@@ -4,6 +4,8 @@ pub use logout::*;
 pub mod login;
 pub mod logout;
 
+use crate::routes::{Res, Role};
+use actix_web::HttpResponse;
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres};
 
@@ -12,6 +14,12 @@ pub struct SessionToken {
     pub token: String,
 }
 
+/// For endpoints that also serve anonymous callers.
+#[derive(Deserialize)]
+pub struct OptionalSessionToken {
+    pub token: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct Session {
     pub token: String,
@@ -31,3 +39,54 @@ pub async fn validate_session_token(
         Err(err) => Err(err),
     }
 }
+
+#[derive(Debug, Serialize)]
+pub struct SessionUser {
+    pub username: String,
+    pub role: Role,
+}
+
+pub async fn get_session_user(
+    session_token: &str,
+    db_pool: &Pool<Postgres>,
+) -> Result<SessionUser, sqlx::Error> {
+    sqlx::query_as!(
+        SessionUser,
+        "SELECT users.username, users.role as \"role: Role\"
+         FROM sessions JOIN users ON users.username = sessions.username
+         WHERE sessions.token = $1",
+        session_token
+    )
+    .fetch_one(db_pool)
+    .await
+}
+
+/// Resolves the session owner, or the response to send back when there is none.
+pub async fn require_session(
+    session_token: &str,
+    db_pool: &Pool<Postgres>,
+) -> Result<SessionUser, HttpResponse> {
+    match get_session_user(session_token, db_pool).await {
+        Ok(user) => Ok(user),
+        Err(sqlx::Error::RowNotFound) => Err(HttpResponse::Unauthorized().json(Res {
+            msg: "unauthorized".to_owned(),
+        })),
+        Err(_) => Err(HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        })),
+    }
+}
+
+/// Same as [`require_session`] but only lets ADMIN sessions through.
+pub async fn require_admin(
+    session_token: &str,
+    db_pool: &Pool<Postgres>,
+) -> Result<SessionUser, HttpResponse> {
+    let user = require_session(session_token, db_pool).await?;
+    match user.role {
+        Role::ADMIN => Ok(user),
+        Role::USER => Err(HttpResponse::Forbidden().json(Res {
+            msg: "forbidden".to_owned(),
+        })),
+    }
+}
@@ -0,0 +1,23 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::get_calendars_by_id;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn get_calendars(
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    match get_calendars_by_id(None, &db_pool.pool).await {
+        Ok(calendars) => HttpResponse::Ok().json(calendars),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
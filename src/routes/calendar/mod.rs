@@ -0,0 +1,123 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::{Acquire, Postgres};
+
+use crate::routes::CalendarException;
+
+pub mod get;
+pub mod post;
+pub mod slug;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Calendar {
+    pub id: i32,
+    pub name: String,
+    pub monday: bool,
+    pub tuesday: bool,
+    pub wednesday: bool,
+    pub thursday: bool,
+    pub friday: bool,
+    pub saturday: bool,
+    pub sunday: bool,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Days added to or removed from the weekday pattern.
+    pub dates: Vec<CalendarDate>,
+}
+
+impl Calendar {
+    /// Same rule as the `trip_runs_on` database function: added and removed
+    /// dates win over the weekday pattern.
+    pub fn runs_on(&self, day: NaiveDate) -> bool {
+        if let Some(date) = self.dates.iter().find(|date| date.date == day) {
+            return date.exception == CalendarException::ADDED;
+        }
+
+        let weekday = match day.weekday() {
+            Weekday::Mon => self.monday,
+            Weekday::Tue => self.tuesday,
+            Weekday::Wed => self.wednesday,
+            Weekday::Thu => self.thursday,
+            Weekday::Fri => self.friday,
+            Weekday::Sat => self.saturday,
+            Weekday::Sun => self.sunday,
+        };
+        weekday && (self.start_date..=self.end_date).contains(&day)
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct CalendarDate {
+    pub date: NaiveDate,
+    pub exception: CalendarException,
+}
+
+#[derive(Deserialize)]
+pub struct CalendarRequest {
+    pub name: String,
+    pub monday: bool,
+    pub tuesday: bool,
+    pub wednesday: bool,
+    pub thursday: bool,
+    pub friday: bool,
+    pub saturday: bool,
+    pub sunday: bool,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+impl CalendarRequest {
+    fn is_valid(&self) -> bool {
+        !self.name.trim().is_empty()
+            && self.name.chars().count() <= 100
+            && self.start_date <= self.end_date
+    }
+}
+
+/// Calendars with their dates, every calendar when `calendar_id` is `None`.
+pub async fn get_calendars_by_id<'c>(
+    calendar_id: Option<i32>,
+    db: impl Acquire<'c, Database = Postgres>,
+) -> Result<Vec<Calendar>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    let calendars = sqlx::query!(
+        "SELECT id, name, monday, tuesday, wednesday, thursday, friday, saturday, sunday,
+         start_date, end_date
+         FROM calendars WHERE ($1::int IS NULL OR id = $1) ORDER BY id",
+        calendar_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let dates = sqlx::query!(
+        "SELECT calendar_id, date, exception as \"exception: CalendarException\"
+         FROM calendar_dates WHERE ($1::int IS NULL OR calendar_id = $1) ORDER BY date",
+        calendar_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(calendars
+        .into_iter()
+        .map(|calendar| Calendar {
+            dates: dates
+                .iter()
+                .filter(|date| date.calendar_id == calendar.id)
+                .map(|date| CalendarDate {
+                    date: date.date,
+                    exception: date.exception,
+                })
+                .collect(),
+            id: calendar.id,
+            name: calendar.name,
+            monday: calendar.monday,
+            tuesday: calendar.tuesday,
+            wednesday: calendar.wednesday,
+            thursday: calendar.thursday,
+            friday: calendar.friday,
+            saturday: calendar.saturday,
+            sunday: calendar.sunday,
+            start_date: calendar.start_date,
+            end_date: calendar.end_date,
+        })
+        .collect())
+}
@@ -0,0 +1,62 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::{Calendar, CalendarRequest};
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn post_calendar(
+    search_param: web::Query<SessionToken>,
+    request: web::Json<CalendarRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "invalid request".to_owned(),
+        });
+    }
+
+    let query = sqlx::query!(
+        "INSERT INTO calendars
+         (name, monday, tuesday, wednesday, thursday, friday, saturday, sunday, start_date, end_date)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         RETURNING id",
+        request.name.trim(),
+        request.monday,
+        request.tuesday,
+        request.wednesday,
+        request.thursday,
+        request.friday,
+        request.saturday,
+        request.sunday,
+        request.start_date,
+        request.end_date
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(record) => HttpResponse::Created().json(Calendar {
+            id: record.id,
+            name: request.name.trim().to_owned(),
+            monday: request.monday,
+            tuesday: request.tuesday,
+            wednesday: request.wednesday,
+            thursday: request.thursday,
+            friday: request.friday,
+            saturday: request.saturday,
+            sunday: request.sunday,
+            start_date: request.start_date,
+            end_date: request.end_date,
+            dates: Vec::new(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
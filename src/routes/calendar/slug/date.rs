@@ -0,0 +1,79 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use super::CalendarDateSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    calendar::CalendarDate,
+    CalendarException, DatabasePool, Res,
+};
+
+#[derive(Deserialize)]
+pub struct CalendarDateRequest {
+    exception: CalendarException,
+}
+
+pub async fn put_calendar_date(
+    slug: web::Path<CalendarDateSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<CalendarDateRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query_as!(
+        CalendarDate,
+        "INSERT INTO calendar_dates (calendar_id, date, exception) VALUES ($1, $2, $3)
+         ON CONFLICT (calendar_id, date) DO UPDATE SET exception = EXCLUDED.exception
+         RETURNING date, exception as \"exception: CalendarException\"",
+        slug.calendar_id,
+        slug.date,
+        request.exception as CalendarException
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(date) => HttpResponse::Ok().json(date),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(Res {
+                msg: "calendar not found".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+pub async fn delete_calendar_date(
+    slug: web::Path<CalendarDateSlug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query!(
+        "DELETE FROM calendar_dates WHERE calendar_id = $1 AND date = $2",
+        slug.calendar_id,
+        slug.date
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "calendar date not found".to_owned(),
+        }),
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "calendar date deleted".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,38 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::CalendarSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn delete_calendar(
+    slug: web::Path<CalendarSlug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query!("DELETE FROM calendars WHERE id = $1", slug.calendar_id)
+        .execute(&db_pool.pool)
+        .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "calendar not found".to_owned(),
+        }),
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "calendar deleted".to_owned(),
+        }),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::Conflict().json(Res {
+                msg: "calendar is used by trips".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
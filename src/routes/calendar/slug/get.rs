@@ -0,0 +1,28 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::CalendarSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    calendar::get_calendars_by_id,
+    DatabasePool, Res,
+};
+
+pub async fn find_calendar(
+    slug: web::Path<CalendarSlug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    match get_calendars_by_id(Some(slug.calendar_id), &db_pool.pool).await {
+        Ok(mut calendars) if !calendars.is_empty() => HttpResponse::Ok().json(calendars.remove(0)),
+        Ok(_) => HttpResponse::NotFound().json(Res {
+            msg: "calendar not found".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
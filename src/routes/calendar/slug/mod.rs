@@ -0,0 +1,18 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+pub mod date;
+pub mod delete;
+pub mod get;
+pub mod put;
+
+#[derive(Deserialize)]
+pub struct CalendarSlug {
+    calendar_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct CalendarDateSlug {
+    calendar_id: i32,
+    date: NaiveDate,
+}
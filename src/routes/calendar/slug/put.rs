@@ -0,0 +1,62 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::CalendarSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    calendar::{get_calendars_by_id, CalendarRequest},
+    DatabasePool, Res,
+};
+
+pub async fn put_calendar(
+    slug: web::Path<CalendarSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<CalendarRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "invalid request".to_owned(),
+        });
+    }
+
+    let query = sqlx::query!(
+        "UPDATE calendars
+         SET name = $2, monday = $3, tuesday = $4, wednesday = $5, thursday = $6, friday = $7,
+         saturday = $8, sunday = $9, start_date = $10, end_date = $11
+         WHERE id = $1",
+        slug.calendar_id,
+        request.name.trim(),
+        request.monday,
+        request.tuesday,
+        request.wednesday,
+        request.thursday,
+        request.friday,
+        request.saturday,
+        request.sunday,
+        request.start_date,
+        request.end_date
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "calendar not found".to_owned(),
+        }),
+        Ok(_) => match get_calendars_by_id(Some(slug.calendar_id), &db_pool.pool).await {
+            Ok(mut calendars) if !calendars.is_empty() => {
+                HttpResponse::Ok().json(calendars.remove(0))
+            }
+            _ => HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            }),
+        },
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,23 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::get_fare_rules;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn get_fares(
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    match get_fare_rules(&db_pool.pool).await {
+        Ok(rules) => HttpResponse::Ok().json(rules),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Acquire, Postgres};
+use std::collections::HashMap;
+
+use crate::routes::{service_time::ServiceTime, Vehicle};
+
+pub mod get;
+pub mod quote;
+pub mod slug;
+
+/// Fares are given in satang of this currency.
+pub const CURRENCY: &str = "THB";
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+pub struct FareRule {
+    #[serde(rename = "type")]
+    pub vehicle: Vehicle,
+    /// Paid once per ride.
+    pub base_fare: i32,
+    pub per_km: i32,
+    /// Paid for every leg that goes into another fare zone.
+    pub per_zone: i32,
+    /// Taken off the base fare of a ride boarded after a transfer.
+    pub transfer_discount: i32,
+}
+
+#[derive(Deserialize)]
+pub struct FareRuleRequest {
+    pub base_fare: i32,
+    pub per_km: i32,
+    #[serde(default)]
+    pub per_zone: i32,
+    #[serde(default)]
+    pub transfer_discount: i32,
+}
+
+impl FareRuleRequest {
+    pub fn is_valid(&self) -> bool {
+        [
+            self.base_fare,
+            self.per_km,
+            self.per_zone,
+            self.transfer_discount,
+        ]
+        .iter()
+        .all(|amount| *amount >= 0)
+    }
+}
+
+/// What one leg adds to the fare, a ride's base fare and transfer discount
+/// are put on the leg it is boarded at.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct LegFare {
+    pub schedule_id: i32,
+    #[serde(rename = "type")]
+    pub vehicle_type: Option<Vehicle>,
+    /// Meters, estimated from speed and travel time when not recorded.
+    pub distance: i32,
+    pub base_fare: i32,
+    pub distance_fare: i32,
+    pub zone_fare: i32,
+    pub transfer_discount: i32,
+    pub total: i32,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Fare {
+    pub currency: String,
+    pub legs: Vec<LegFare>,
+    pub total: i32,
+}
+
+/// A leg with what its fare depends on.
+#[derive(Debug, Clone)]
+pub struct FareLeg {
+    pub id: i32,
+    pub trip_id: i32,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub vehicle_type: Option<Vehicle>,
+    pub distance: Option<i32>,
+    pub speed: Option<i32>,
+    pub departure_time: Option<ServiceTime>,
+    pub arrival_time: Option<ServiceTime>,
+    pub from_zone: Option<String>,
+    pub to_zone: Option<String>,
+}
+
+impl FareLeg {
+    fn distance(&self) -> i32 {
+        self.distance.unwrap_or_else(|| {
+            match (self.speed, self.departure_time, self.arrival_time) {
+                (Some(speed), Some(departure), Some(arrival)) => {
+                    // km/h over seconds, in meters
+                    let seconds = (arrival.seconds() - departure.seconds()).max(0);
+                    (i64::from(speed) * i64::from(seconds) * 1000 / 3600) as i32
+                }
+                _ => 0,
+            }
+        })
+    }
+}
+
+/// Fare of riding `legs` in order. Consecutive legs of the same trip make a
+/// single ride, legs without a matching rule are free.
+pub fn calculate_fare(legs: &[FareLeg], rules: &[FareRule]) -> Fare {
+    let rules: HashMap<Vehicle, &FareRule> =
+        rules.iter().map(|rule| (rule.vehicle, rule)).collect();
+
+    let mut leg_fares = Vec::new();
+    let mut prev_trip = None;
+    for leg in legs {
+        let rule = leg.vehicle_type.and_then(|vehicle| rules.get(&vehicle));
+        let boarding = prev_trip != Some(leg.trip_id);
+        let transfer = boarding && prev_trip.is_some();
+        prev_trip = Some(leg.trip_id);
+
+        let distance = leg.distance();
+        let (base_fare, distance_fare, zone_fare, transfer_discount) = match rule {
+            Some(rule) => {
+                let base_fare = if boarding { rule.base_fare } else { 0 };
+                let zone_change = matches!(
+                    (&leg.from_zone, &leg.to_zone),
+                    (Some(from), Some(to)) if from != to
+                );
+                (
+                    base_fare,
+                    (i64::from(rule.per_km) * i64::from(distance) / 1000) as i32,
+                    if zone_change { rule.per_zone } else { 0 },
+                    if transfer {
+                        rule.transfer_discount.min(base_fare)
+                    } else {
+                        0
+                    },
+                )
+            }
+            None => (0, 0, 0, 0),
+        };
+
+        leg_fares.push(LegFare {
+            schedule_id: leg.id,
+            vehicle_type: leg.vehicle_type,
+            distance,
+            base_fare,
+            distance_fare,
+            zone_fare,
+            transfer_discount,
+            total: base_fare + distance_fare + zone_fare - transfer_discount,
+        });
+    }
+
+    Fare {
+        currency: CURRENCY.to_owned(),
+        total: leg_fares.iter().map(|leg| leg.total).sum(),
+        legs: leg_fares,
+    }
+}
+
+pub async fn get_fare_rules<'c>(
+    db: impl Acquire<'c, Database = Postgres>,
+) -> Result<Vec<FareRule>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    sqlx::query_as!(
+        FareRule,
+        "SELECT vehicle as \"vehicle: Vehicle\", base_fare, per_km, per_zone, transfer_discount
+         FROM fare_rules ORDER BY vehicle"
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Legs by schedule id, the vehicle falling back to the one of the line.
+pub async fn get_fare_legs<'c>(
+    schedule_ids: &[i32],
+    db: impl Acquire<'c, Database = Postgres>,
+) -> Result<HashMap<i32, FareLeg>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    let legs = sqlx::query_as!(
+        FareLeg,
+        "SELECT s.id, s.trip_id, s.from_place_id, s.to_place_id,
+         COALESCE(s.type, lines.type) as \"vehicle_type: Vehicle\", s.distance, s.speed,
+         s.departure_time as \"departure_time: ServiceTime\",
+         s.arrival_time as \"arrival_time: ServiceTime\",
+         from_place.fare_zone as from_zone, to_place.fare_zone as to_zone
+         FROM schedules s
+         JOIN lines ON lines.id = s.line
+         LEFT JOIN places from_place ON from_place.id = s.from_place_id
+         LEFT JOIN places to_place ON to_place.id = s.to_place_id
+         WHERE s.id = ANY($1)",
+        schedule_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(legs.into_iter().map(|leg| (leg.id, leg)).collect())
+}
@@ -0,0 +1,68 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use super::{calculate_fare, get_fare_legs, get_fare_rules, FareLeg};
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Res,
+};
+
+#[derive(Deserialize)]
+pub struct QuoteRequest {
+    /// Legs in the order they are ridden.
+    pub schedule_ids: Vec<i32>,
+}
+
+pub async fn quote(
+    search_param: web::Query<SessionToken>,
+    request: web::Json<QuoteRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    if request.schedule_ids.is_empty() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "give at least one schedule_id".to_owned(),
+        });
+    }
+
+    let (rules, mut legs) = match (
+        get_fare_rules(&db_pool.pool).await,
+        get_fare_legs(&request.schedule_ids, &db_pool.pool).await,
+    ) {
+        (Ok(rules), Ok(legs)) => (rules, legs),
+        _ => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    };
+
+    let mut itinerary: Vec<FareLeg> = Vec::new();
+    for schedule_id in &request.schedule_ids {
+        let leg = match legs.remove(schedule_id) {
+            Some(leg) => leg,
+            None => {
+                return HttpResponse::BadRequest().json(Res {
+                    msg: format!("unknown or repeated schedule {}", schedule_id),
+                })
+            }
+        };
+        if itinerary
+            .last()
+            .is_some_and(|prev| prev.to_place_id != leg.from_place_id)
+        {
+            return HttpResponse::BadRequest().json(Res {
+                msg: format!(
+                    "schedule {} does not leave where the leg before ends",
+                    schedule_id
+                ),
+            });
+        }
+        itinerary.push(leg);
+    }
+
+    HttpResponse::Ok().json(calculate_fare(&itinerary, &rules))
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+use crate::routes::Vehicle;
+
+pub mod put;
+
+#[derive(Deserialize)]
+pub struct FareSlug {
+    vehicle: Vehicle,
+}
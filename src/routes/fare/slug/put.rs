@@ -0,0 +1,49 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::FareSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    fare::{FareRule, FareRuleRequest},
+    DatabasePool, Res, Vehicle,
+};
+
+pub async fn put_fare(
+    slug: web::Path<FareSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<FareRuleRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "fare amounts must not be negative".to_owned(),
+        });
+    }
+
+    let query = sqlx::query_as!(
+        FareRule,
+        "INSERT INTO fare_rules (vehicle, base_fare, per_km, per_zone, transfer_discount)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (vehicle) DO UPDATE SET base_fare = EXCLUDED.base_fare,
+         per_km = EXCLUDED.per_km, per_zone = EXCLUDED.per_zone,
+         transfer_discount = EXCLUDED.transfer_discount
+         RETURNING vehicle as \"vehicle: Vehicle\", base_fare, per_km, per_zone, transfer_discount",
+        slug.vehicle as Vehicle,
+        request.base_fare,
+        request.per_km,
+        request.per_zone,
+        request.transfer_discount
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(rule) => HttpResponse::Ok().json(rule),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
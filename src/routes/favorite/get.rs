@@ -0,0 +1,35 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::FavoritePlace;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn get_favorite_places(
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    let query = sqlx::query_as!(
+        FavoritePlace,
+        "SELECT place_id, places.name, places.image_path, favorite_places.created_at
+         FROM favorite_places JOIN places ON places.id = favorite_places.place_id
+         WHERE username = $1
+         ORDER BY favorite_places.created_at DESC",
+        user.username
+    )
+    .fetch_all(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(favorites) => HttpResponse::Ok().json(favorites),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub mod get;
+pub mod slug;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct FavoritePlace {
+    pub place_id: i32,
+    pub name: Option<String>,
+    pub image_path: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
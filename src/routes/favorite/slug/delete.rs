@@ -0,0 +1,38 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    place::slug::Slug,
+    DatabasePool, Res,
+};
+
+pub async fn delete_favorite_place(
+    slug: web::Path<Slug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    let query = sqlx::query!(
+        "DELETE FROM favorite_places WHERE username = $1 AND place_id = $2",
+        user.username,
+        slug.id
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "favorite not found".to_owned(),
+        }),
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "place removed from favorites".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
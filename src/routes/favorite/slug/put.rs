@@ -0,0 +1,41 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    place::slug::Slug,
+    DatabasePool, Res,
+};
+
+pub async fn put_favorite_place(
+    slug: web::Path<Slug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    let query = sqlx::query!(
+        "INSERT INTO favorite_places (username, place_id) VALUES ($1, $2)
+         ON CONFLICT DO NOTHING",
+        user.username,
+        slug.id
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "place added to favorites".to_owned(),
+        }),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(Res {
+                msg: "place not found".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,31 @@
+use actix_web::{http::header, web, HttpResponse, Responder};
+
+use crate::{
+    gtfs::export::export_feed,
+    routes::{
+        auth::{require_session, SessionToken},
+        DatabasePool, Res,
+    },
+};
+
+pub async fn export_gtfs(
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    match export_feed(&db_pool.pool).await {
+        Ok(feed) => HttpResponse::Ok()
+            .content_type("application/zip")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"gtfs.zip\"",
+            ))
+            .body(feed),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
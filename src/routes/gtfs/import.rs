@@ -0,0 +1,50 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::DryRunParam;
+use crate::{
+    gtfs::{
+        import::{apply_import, plan_import},
+        Feed,
+    },
+    routes::{
+        auth::{require_admin, SessionToken},
+        DatabasePool, Res,
+    },
+};
+
+/// Largest GTFS zip accepted by the upload endpoint.
+pub const MAX_FEED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Imports what is valid of the feed, reporting the rest. Only a feed that
+/// could not be imported at all is a 422, a client seeing it knows nothing
+/// was written.
+pub async fn import_gtfs(
+    search_param: web::Query<SessionToken>,
+    dry_run_param: web::Query<DryRunParam>,
+    body: web::Bytes,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let feed = match Feed::read(&body) {
+        Ok(feed) => feed,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(Res {
+                msg: "body is not a GTFS zip".to_owned(),
+            })
+        }
+    };
+
+    let plan = plan_import(&feed);
+    match apply_import(plan, dry_run_param.dry_run, &db_pool.pool).await {
+        Ok(report) if report.has_errors() && report.imported_nothing() => {
+            HttpResponse::UnprocessableEntity().json(report)
+        }
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,11 @@
+use serde::Deserialize;
+
+pub mod export;
+pub mod import;
+pub mod realtime;
+
+#[derive(Deserialize)]
+pub struct DryRunParam {
+    #[serde(default)]
+    pub dry_run: bool,
+}
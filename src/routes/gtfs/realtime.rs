@@ -0,0 +1,91 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gtfs::realtime::{read_trip_updates, SkippedEntity},
+    routes::{
+        auth::{require_admin, SessionToken},
+        realtime::{expires_at, get_trip_legs, LegUpdate, RealtimeStore, TripLegs, MAX_TTL},
+        DatabasePool, Res,
+    },
+};
+
+/// Largest GTFS-Realtime message accepted by the upload endpoint.
+pub const MAX_REALTIME_FEED_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct TtlParam {
+    /// Seconds the updates are kept, defaults to `DEFAULT_TTL`.
+    ttl: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RealtimeReport {
+    pub updates: Vec<LegUpdate>,
+    pub skipped: Vec<SkippedEntity>,
+}
+
+/// Takes a GTFS-Realtime `FeedMessage` with trip updates in its body.
+pub async fn import_gtfs_realtime(
+    search_param: web::Query<SessionToken>,
+    ttl_param: web::Query<TtlParam>,
+    body: web::Bytes,
+    realtime: web::Data<RealtimeStore>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let expires_at = match expires_at(ttl_param.ttl) {
+        Some(expires_at) => expires_at,
+        None => {
+            return HttpResponse::BadRequest().json(Res {
+                msg: format!("ttl must be between 1 and {}", MAX_TTL),
+            })
+        }
+    };
+
+    let (trip_updates, mut skipped) = match read_trip_updates(&body, &db_pool.pool).await {
+        Ok(read) => read,
+        Err(err) if err.is::<prost::DecodeError>() => {
+            return HttpResponse::BadRequest().json(Res {
+                msg: "body is not a GTFS-Realtime feed".to_owned(),
+            })
+        }
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    };
+
+    let trip_ids: Vec<i32> = trip_updates.iter().map(|trip| trip.trip_id).collect();
+    let trip_legs = match get_trip_legs(&trip_ids, &db_pool.pool).await {
+        Ok(trip_legs) => trip_legs,
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    };
+
+    let today = Local::now().date_naive();
+    let no_legs = TripLegs::default();
+    let mut updates = Vec::new();
+    for trip in trip_updates {
+        let legs = trip_legs.get(&trip.trip_id).unwrap_or(&no_legs);
+        match trip.leg_updates(legs, today, expires_at) {
+            Ok(leg_updates) => updates.extend(leg_updates),
+            Err(reason) => skipped.push(SkippedEntity {
+                entity_id: format!("T{}", trip.trip_id),
+                reason,
+            }),
+        }
+    }
+
+    realtime.insert(&updates);
+    updates.sort_by_key(|update| (update.schedule_id, update.service_date));
+    HttpResponse::Ok().json(RealtimeReport { updates, skipped })
+}
@@ -0,0 +1,27 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+
+use super::get_lines_by_id;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    locale::{preferred_langs, LangParam},
+    DatabasePool, Res,
+};
+
+pub async fn get_lines(
+    search_param: web::Query<SessionToken>,
+    lang_param: web::Query<LangParam>,
+    http_req: HttpRequest,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let langs = preferred_langs(&http_req, &lang_param);
+    match get_lines_by_id(None, &langs, &db_pool.pool).await {
+        Ok(lines) => HttpResponse::Ok().json(lines),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+
+use crate::routes::{
+    place::{get::get_localized_places_by_ids, Place},
+    service_time::ServiceTime,
+    Vehicle,
+};
+
+pub mod get;
+pub mod slug;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Line {
+    pub id: i32,
+    pub short_name: Option<String>,
+    pub long_name: Option<String>,
+    pub color: Option<String>,
+    #[serde(rename = "type")]
+    pub vehicle_type: Option<Vehicle>,
+    /// Stops of the longest run of the line, in travel order.
+    pub stops: Vec<Place>,
+    pub first_departure: Option<ServiceTime>,
+    pub last_departure: Option<ServiceTime>,
+    /// Median minutes between runs leaving the first stop.
+    pub headway: Option<i64>,
+}
+
+struct LineInfo {
+    id: i32,
+    short_name: Option<String>,
+    long_name: Option<String>,
+    color: Option<String>,
+    vehicle_type: Option<Vehicle>,
+}
+
+/// One run of a line as the places it stops at and the time it leaves each of
+/// them, the last time being the arrival at the terminus.
+pub struct LineTrip {
+    pub id: i32,
+    pub line: i32,
+    pub stops: Vec<i32>,
+    pub times: Vec<ServiceTime>,
+}
+
+pub async fn get_line_trips(
+    line: Option<i32>,
+    db_pool: &Pool<Postgres>,
+) -> Result<Vec<LineTrip>, sqlx::Error> {
+    let legs = sqlx::query!(
+        "SELECT trips.id, trips.line, from_place_id, to_place_id,
+         departure_time as \"departure_time!: ServiceTime\",
+         arrival_time as \"arrival_time!: ServiceTime\"
+         FROM schedules
+         JOIN trips ON trips.id = schedules.trip_id
+         WHERE ($1::int IS NULL OR trips.line = $1)
+         AND departure_time IS NOT NULL AND arrival_time IS NOT NULL
+         ORDER BY trips.id, stop_sequence",
+        line
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut trips: Vec<LineTrip> = Vec::new();
+    for leg in legs {
+        match trips.last_mut() {
+            Some(trip) if trip.id == leg.id => {
+                trip.times.pop();
+                trip.times.push(leg.departure_time);
+            }
+            _ => trips.push(LineTrip {
+                id: leg.id,
+                line: leg.line,
+                stops: vec![leg.from_place_id],
+                times: vec![leg.departure_time],
+            }),
+        }
+        let trip = trips.last_mut().expect("a trip was just pushed");
+        trip.stops.push(leg.to_place_id);
+        trip.times.push(leg.arrival_time);
+    }
+
+    Ok(trips)
+}
+
+/// Stops of the run with the most stops, the earliest one on a tie.
+pub fn stop_pattern(trips: &[&LineTrip]) -> Vec<i32> {
+    trips
+        .iter()
+        .max_by_key(|trip| (trip.stops.len(), std::cmp::Reverse(trip.id)))
+        .map(|trip| trip.stops.clone())
+        .unwrap_or_default()
+}
+
+/// Time `trip` leaves each stop of `pattern`, matching stops in order so loops
+/// and partial runs line up.
+pub fn times_along(pattern: &[i32], trip: &LineTrip) -> Vec<Option<ServiceTime>> {
+    let mut next = 0;
+    pattern
+        .iter()
+        .map(|place_id| {
+            let found = trip.stops[next..]
+                .iter()
+                .position(|stop| stop == place_id)?;
+            next += found + 1;
+            Some(trip.times[next - 1])
+        })
+        .collect()
+}
+
+/// Median gap in minutes between consecutive departures.
+pub fn headway(mut departures: Vec<ServiceTime>) -> Option<i64> {
+    departures.sort();
+    let mut gaps: Vec<i64> = departures
+        .windows(2)
+        .map(|pair| i64::from(pair[1].seconds() - pair[0].seconds()) / 60)
+        .filter(|gap| *gap > 0)
+        .collect();
+    gaps.sort();
+
+    gaps.get(gaps.len() / 2).copied()
+}
+
+/// Places in the order of `place_ids`, repeating the ones a loop visits twice.
+pub fn ordered_places(place_ids: &[i32], places: &HashMap<i32, Place>) -> Vec<Place> {
+    place_ids
+        .iter()
+        .filter_map(|place_id| places.get(place_id).cloned())
+        .collect()
+}
+
+/// Lines with their stops and service span, every line when `line` is `None`.
+pub async fn get_lines_by_id(
+    line: Option<i32>,
+    langs: &[String],
+    db_pool: &Pool<Postgres>,
+) -> Result<Vec<Line>, sqlx::Error> {
+    let infos = sqlx::query_as!(
+        LineInfo,
+        "SELECT id, short_name, long_name, color, type as \"vehicle_type: Vehicle\"
+         FROM lines WHERE ($1::int IS NULL OR id = $1) ORDER BY id",
+        line
+    )
+    .fetch_all(db_pool)
+    .await?;
+    let trips = get_line_trips(line, db_pool).await?;
+    let mut trips_by_line: HashMap<i32, Vec<&LineTrip>> = HashMap::new();
+    for trip in &trips {
+        trips_by_line.entry(trip.line).or_default().push(trip);
+    }
+    let patterns: Vec<Vec<i32>> = infos
+        .iter()
+        .map(|info| stop_pattern(trips_by_line.get(&info.id).map_or(&[][..], Vec::as_slice)))
+        .collect();
+    let place_ids: Vec<i32> = patterns.iter().flatten().copied().collect();
+    let places: HashMap<i32, Place> = get_localized_places_by_ids(&place_ids, langs, db_pool)
+        .await?
+        .into_iter()
+        .map(|place| (place.id, place))
+        .collect();
+
+    let mut lines = Vec::new();
+    for (info, pattern) in infos.into_iter().zip(patterns) {
+        let line_trips = trips_by_line.get(&info.id).map_or(&[][..], Vec::as_slice);
+        let first_times: Vec<ServiceTime> = line_trips.iter().map(|trip| trip.times[0]).collect();
+        let headway_times = line_trips
+            .iter()
+            .filter(|trip| pattern.first() == trip.stops.first())
+            .map(|trip| trip.times[0])
+            .collect();
+
+        lines.push(Line {
+            id: info.id,
+            short_name: info.short_name,
+            long_name: info.long_name,
+            color: info.color,
+            vehicle_type: info.vehicle_type,
+            stops: ordered_places(&pattern, &places),
+            first_departure: first_times.iter().min().copied(),
+            last_departure: first_times.iter().max().copied(),
+            headway: headway(headway_times),
+        });
+    }
+
+    Ok(lines)
+}
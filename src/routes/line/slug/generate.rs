@@ -0,0 +1,304 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+
+use super::LineSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    gtfs::DryRunParam,
+    schedule::ensure_line,
+    service_time::ServiceTime,
+    DatabasePool, Res, Vehicle,
+};
+
+/// Most runs a single request may generate.
+pub const MAX_GENERATED_TRIPS: usize = 500;
+/// Longest run time, dwell or headway in minutes.
+const MAX_MINUTES: i32 = 24 * 60;
+
+/// Template of a line: runs leave the first stop every `headway` minutes from
+/// `first_departure` up to `last_departure` and take the same time between
+/// stops.
+#[derive(Deserialize)]
+pub struct GenerateRequest {
+    #[serde(rename = "type")]
+    pub vehicle_type: Option<Vehicle>,
+    /// Places the runs stop at, in travel order.
+    pub stops: Vec<i32>,
+    /// Minutes from each stop to the next one.
+    pub run_times: Vec<i32>,
+    /// Minutes a run waits at every stop between the first and the last.
+    #[serde(default)]
+    pub dwell: i32,
+    pub first_departure: ServiceTime,
+    pub last_departure: ServiceTime,
+    pub headway: i32,
+    /// Meters from each stop to the next one, speeds are derived from them.
+    pub distances: Option<Vec<i32>>,
+    pub calendar_id: Option<i32>,
+}
+
+impl GenerateRequest {
+    /// Returns what is wrong with the request, leaving out database checks.
+    fn error(&self) -> Option<&'static str> {
+        let segments = self.stops.len().saturating_sub(1);
+        if segments == 0 {
+            return Some("give at least two stops");
+        }
+        if self.stops.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Some("consecutive stops must differ");
+        }
+        if self.run_times.len() != segments {
+            return Some("give one run time per pair of stops");
+        }
+        if self
+            .run_times
+            .iter()
+            .any(|minutes| !(1..=MAX_MINUTES).contains(minutes))
+        {
+            return Some("run times must be between 1 and 1440 minutes");
+        }
+        if self
+            .distances
+            .as_ref()
+            .is_some_and(|distances| distances.len() != segments)
+        {
+            return Some("give one distance per pair of stops");
+        }
+        if self
+            .distances
+            .iter()
+            .flatten()
+            .any(|distance| *distance < 0)
+        {
+            return Some("distances must not be negative");
+        }
+        if !(0..=MAX_MINUTES).contains(&self.dwell) {
+            return Some("dwell must be between 0 and 1440 minutes");
+        }
+        if !(1..=MAX_MINUTES).contains(&self.headway) {
+            return Some("headway must be between 1 and 1440 minutes");
+        }
+        if self.first_departure > self.last_departure {
+            return Some("first_departure must not be after last_departure");
+        }
+        let runs = (self.last_departure.seconds() - self.first_departure.seconds())
+            / (self.headway * 60)
+            + 1;
+        if runs as usize > MAX_GENERATED_TRIPS {
+            return Some("too many runs, use a longer headway");
+        }
+        if self.trip_duration() + i64::from(self.last_departure.seconds())
+            >= i64::from(2 * ServiceTime::DAY)
+        {
+            return Some("runs must end before 48:00");
+        }
+
+        None
+    }
+
+    /// Seconds from leaving the first stop to arriving at the last one.
+    fn trip_duration(&self) -> i64 {
+        let travel: i64 = self
+            .run_times
+            .iter()
+            .map(|minutes| i64::from(*minutes))
+            .sum();
+        let dwells = i64::from(self.dwell) * (self.run_times.len() as i64 - 1);
+        (travel + dwells) * 60
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GeneratedLeg {
+    /// Schedule id, `None` in a preview.
+    pub id: Option<i32>,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub departure_time: ServiceTime,
+    pub arrival_time: ServiceTime,
+    pub distance: Option<i32>,
+    pub speed: Option<i32>,
+    pub stop_sequence: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GeneratedTrip {
+    /// `None` in a preview.
+    pub trip_id: Option<i32>,
+    pub legs: Vec<GeneratedLeg>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GeneratedTimetable {
+    pub line: i32,
+    pub dry_run: bool,
+    pub trips: Vec<GeneratedTrip>,
+}
+
+pub async fn generate_line_timetable(
+    slug: web::Path<LineSlug>,
+    search_param: web::Query<SessionToken>,
+    dry_run_param: web::Query<DryRunParam>,
+    request: web::Json<GenerateRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    if let Some(msg) = request.error() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: msg.to_owned(),
+        });
+    }
+    match references_exist(&request, &db_pool.pool).await {
+        Ok(None) => {}
+        Ok(Some(msg)) => {
+            return HttpResponse::BadRequest().json(Res {
+                msg: msg.to_owned(),
+            })
+        }
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    }
+
+    let mut timetable = GeneratedTimetable {
+        line: slug.line_id,
+        dry_run: dry_run_param.dry_run,
+        trips: plan_trips(&request),
+    };
+    if timetable.dry_run {
+        return HttpResponse::Ok().json(timetable);
+    }
+
+    match insert_trips(slug.line_id, &request, &mut timetable.trips, &db_pool.pool).await {
+        Ok(()) => HttpResponse::Created().json(timetable),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+async fn references_exist(
+    request: &GenerateRequest,
+    db_pool: &Pool<Postgres>,
+) -> Result<Option<&'static str>, sqlx::Error> {
+    let mut place_ids = request.stops.clone();
+    place_ids.sort_unstable();
+    place_ids.dedup();
+    let found = sqlx::query_scalar!("SELECT COUNT(*) FROM places WHERE id = ANY($1)", &place_ids)
+        .fetch_one(db_pool)
+        .await?
+        .unwrap_or(0);
+    if found as usize != place_ids.len() {
+        return Ok(Some("unknown place"));
+    }
+
+    if let Some(calendar_id) = request.calendar_id {
+        let calendar = sqlx::query_scalar!("SELECT id FROM calendars WHERE id = $1", calendar_id)
+            .fetch_optional(db_pool)
+            .await?;
+        if calendar.is_none() {
+            return Ok(Some("unknown calendar"));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Runs of the template in departure order.
+fn plan_trips(request: &GenerateRequest) -> Vec<GeneratedTrip> {
+    let mut trips = Vec::new();
+    let mut start = request.first_departure.seconds();
+    while start <= request.last_departure.seconds() {
+        let mut departure = start;
+        let legs = request
+            .stops
+            .windows(2)
+            .zip(&request.run_times)
+            .enumerate()
+            .map(|(i, (pair, minutes))| {
+                let arrival = departure + minutes * 60;
+                let distance = request.distances.as_ref().map(|distances| distances[i]);
+                let leg = GeneratedLeg {
+                    id: None,
+                    from_place_id: pair[0],
+                    to_place_id: pair[1],
+                    departure_time: ServiceTime(departure),
+                    arrival_time: ServiceTime(arrival),
+                    distance,
+                    // km/h, rounded
+                    speed: distance
+                        .map(|distance| {
+                            let (distance, minutes) = (i64::from(distance), i64::from(*minutes));
+                            ((distance * 60 + minutes * 500) / (minutes * 1000)) as i32
+                        })
+                        .filter(|speed| *speed > 0),
+                    stop_sequence: i as i32 + 1,
+                };
+                departure = arrival + request.dwell * 60;
+                leg
+            })
+            .collect();
+
+        trips.push(GeneratedTrip {
+            trip_id: None,
+            legs,
+        });
+        start += request.headway * 60;
+    }
+
+    trips
+}
+
+/// Inserts the runs in one transaction, filling in their ids.
+async fn insert_trips(
+    line: i32,
+    request: &GenerateRequest,
+    trips: &mut [GeneratedTrip],
+    db_pool: &Pool<Postgres>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    ensure_line(line, request.vehicle_type, &mut tx).await?;
+
+    for trip in trips.iter_mut() {
+        let trip_id = sqlx::query_scalar!(
+            "INSERT INTO trips (line, calendar_id) VALUES ($1, $2) RETURNING id",
+            line,
+            request.calendar_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        trip.trip_id = Some(trip_id);
+
+        for leg in &mut trip.legs {
+            let id = sqlx::query_scalar!(
+                "INSERT INTO schedules
+                 (line, from_place_id, to_place_id, type, departure_time, arrival_time, distance,
+                 speed, trip_id, stop_sequence)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 RETURNING id",
+                line,
+                leg.from_place_id,
+                leg.to_place_id,
+                request.vehicle_type as Option<Vehicle>,
+                leg.departure_time.seconds(),
+                leg.arrival_time.seconds(),
+                leg.distance,
+                leg.speed,
+                trip_id,
+                leg.stop_sequence
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            leg.id = Some(id);
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+
+use super::LineSlug;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    line::get_lines_by_id,
+    locale::{preferred_langs, LangParam},
+    DatabasePool, Res,
+};
+
+pub async fn find_line(
+    slug: web::Path<LineSlug>,
+    search_param: web::Query<SessionToken>,
+    lang_param: web::Query<LangParam>,
+    http_req: HttpRequest,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let langs = preferred_langs(&http_req, &lang_param);
+    match get_lines_by_id(Some(slug.line_id), &langs, &db_pool.pool).await {
+        Ok(mut lines) if !lines.is_empty() => HttpResponse::Ok().json(lines.remove(0)),
+        Ok(_) => HttpResponse::NotFound().json(Res {
+            msg: "line not found".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+pub mod generate;
+pub mod get;
+pub mod timetable;
+
+#[derive(Deserialize)]
+pub struct LineSlug {
+    line_id: i32,
+}
@@ -0,0 +1,95 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+
+use super::LineSlug;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    line::{get_line_trips, stop_pattern, times_along, LineTrip},
+    locale::{preferred_langs, LangParam},
+    place::{get::get_localized_places_by_ids, Place},
+    service_time::ServiceTime,
+    DatabasePool, Res,
+};
+
+/// Printable timetable: one row per stop, one column per run.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Timetable {
+    pub line: i32,
+    pub trip_ids: Vec<i32>,
+    pub rows: Vec<TimetableRow>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimetableRow {
+    pub place: Place,
+    /// Departure of each run, `None` where the run skips the stop.
+    pub times: Vec<Option<ServiceTime>>,
+}
+
+pub async fn get_line_timetable(
+    slug: web::Path<LineSlug>,
+    search_param: web::Query<SessionToken>,
+    lang_param: web::Query<LangParam>,
+    http_req: HttpRequest,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let langs = preferred_langs(&http_req, &lang_param);
+    match build_timetable(slug.line_id, &langs, &db_pool.pool).await {
+        Ok(Some(timetable)) => HttpResponse::Ok().json(timetable),
+        Ok(None) => HttpResponse::NotFound().json(Res {
+            msg: "line not found".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+async fn build_timetable(
+    line: i32,
+    langs: &[String],
+    db_pool: &Pool<Postgres>,
+) -> Result<Option<Timetable>, sqlx::Error> {
+    let exists = sqlx::query_scalar!("SELECT id FROM lines WHERE id = $1", line)
+        .fetch_optional(db_pool)
+        .await?;
+    if exists.is_none() {
+        return Ok(None);
+    }
+
+    let mut trips = get_line_trips(Some(line), db_pool).await?;
+    trips.sort_by_key(|trip| (trip.times[0], trip.id));
+    let pattern = stop_pattern(&trips.iter().collect::<Vec<&LineTrip>>());
+    let columns: Vec<Vec<Option<ServiceTime>>> = trips
+        .iter()
+        .map(|trip| times_along(&pattern, trip))
+        .collect();
+
+    let places: HashMap<i32, Place> = get_localized_places_by_ids(&pattern, langs, db_pool)
+        .await?
+        .into_iter()
+        .map(|place| (place.id, place))
+        .collect();
+    let rows = pattern
+        .iter()
+        .enumerate()
+        .filter_map(|(i, place_id)| {
+            Some(TimetableRow {
+                place: places.get(place_id)?.clone(),
+                times: columns.iter().map(|column| column[i]).collect(),
+            })
+        })
+        .collect();
+
+    Ok(Some(Timetable {
+        line,
+        trip_ids: trips.iter().map(|trip| trip.id).collect(),
+        rows,
+    }))
+}
@@ -0,0 +1,45 @@
+use actix_web::{
+    http::header::{AcceptLanguage, Header, Preference},
+    HttpRequest,
+};
+use serde::Deserialize;
+
+/// Language stored in `places.name` and `places.description`.
+pub const DEFAULT_LANG: &str = "en";
+
+#[derive(Deserialize)]
+pub struct LangParam {
+    pub lang: Option<String>,
+}
+
+/// Translations to try in order of preference: the `lang` parameter first, then
+/// `Accept-Language` by q-factor. Anything ranked below the default language is
+/// dropped since the untranslated text wins from there on.
+pub fn preferred_langs(req: &HttpRequest, lang_param: &LangParam) -> Vec<String> {
+    let mut langs: Vec<String> = Vec::new();
+
+    if let Some(lang) = &lang_param.lang {
+        langs.push(lang.trim().to_lowercase());
+    }
+
+    if let Ok(accept_language) = AcceptLanguage::parse(req) {
+        for preference in accept_language.ranked() {
+            if let Preference::Specific(tag) = preference {
+                langs.push(tag.as_str().to_lowercase());
+                langs.push(tag.primary_language().to_lowercase());
+            }
+        }
+    }
+
+    let mut preferred = Vec::new();
+    for lang in langs.into_iter().filter(|lang| !lang.is_empty()) {
+        if lang == DEFAULT_LANG {
+            break;
+        }
+        if !preferred.contains(&lang) {
+            preferred.push(lang);
+        }
+    }
+
+    preferred
+}
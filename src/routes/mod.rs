@@ -1,9 +1,22 @@
 pub mod auth;
+pub mod calendar;
+pub mod fare;
+pub mod favorite;
+pub mod gtfs;
+pub mod line;
+pub mod locale;
 pub mod place;
+pub mod realtime;
+pub mod review;
 pub mod route;
+pub mod saved_route;
+pub mod schedule;
+pub mod service_time;
+pub mod transfer;
+pub mod trip;
 
 use serde::{Deserialize, Serialize};
-use sqlx::postgres::PgPool;
+use sqlx::{postgres::PgPool, Pool, Postgres, Transaction};
 
 #[derive(sqlx::Type, Debug, Clone, Copy, Deserialize, Serialize)]
 #[sqlx(type_name = "role")]
@@ -12,11 +25,69 @@ pub enum Role {
     ADMIN,
 }
 
+#[derive(sqlx::Type, Debug, Clone, Copy, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[sqlx(type_name = "vehicle")]
+pub enum Vehicle {
+    TRAIN,
+    BUS,
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[sqlx(type_name = "availability_status")]
+pub enum AvailabilityStatus {
+    AVAILABLE,
+    UNAVAILABLE,
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[sqlx(type_name = "calendar_exception")]
+pub enum CalendarException {
+    ADDED,
+    REMOVED,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Res {
     pub msg: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Pagination {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl Pagination {
+    pub const MAX_PER_PAGE: i64 = 100;
+
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> i64 {
+        self.per_page.unwrap_or(20).clamp(1, Self::MAX_PER_PAGE)
+    }
+
+    /// Rows skipped, pages far past the last one skip everything rather than
+    /// overflow.
+    pub fn offset(&self) -> i64 {
+        (self.page() - 1).saturating_mul(self.per_page())
+    }
+}
+
 pub struct DatabasePool {
     pub pool: PgPool,
 }
+
+/// Transaction whose queries all see the database as it was when the first
+/// one ran, for reads spread over many queries.
+pub async fn begin_snapshot(
+    db_pool: &Pool<Postgres>,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    Ok(tx)
+}
@@ -1,14 +1,17 @@
 use crate::routes::auth::validate_session_token;
+use crate::routes::locale::{preferred_langs, LangParam};
 use crate::routes::place::{Place, Places};
 use crate::{
     routes::auth::SessionToken,
     routes::{DatabasePool, Res},
 };
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use sqlx::{Pool, Postgres};
 
 pub async fn get_places(
     req: web::Query<SessionToken>,
+    lang_param: web::Query<LangParam>,
+    http_req: HttpRequest,
     db_pool: web::Data<DatabasePool>,
 ) -> impl Responder {
     let token = req.token.to_owned();
@@ -22,7 +25,8 @@ pub async fn get_places(
         }),
     };
 
-    match query_places(db_pool.pool.clone()).await {
+    let langs = preferred_langs(&http_req, &lang_param);
+    match query_places(db_pool.pool.clone(), &langs).await {
         Ok(places) => HttpResponse::Ok().json(places),
         Err(_) => HttpResponse::InternalServerError().json(Res {
             msg: "server err".to_owned(),
@@ -30,16 +34,42 @@ pub async fn get_places(
     }
 }
 
-async fn query_places(db_pool: Pool<Postgres>) -> Result<Places, sqlx::Error> {
-    let query = sqlx::query_as!(
+async fn query_places(db_pool: Pool<Postgres>, langs: &[String]) -> Result<Places, sqlx::Error> {
+    Ok(Places(query_localized_places(None, langs, &db_pool).await?))
+}
+
+/// Localized places for `place_ids`, in no particular order.
+pub async fn get_localized_places_by_ids(
+    place_ids: &[i32],
+    langs: &[String],
+    db_pool: &Pool<Postgres>,
+) -> Result<Vec<Place>, sqlx::Error> {
+    query_localized_places(Some(place_ids), langs, db_pool).await
+}
+
+/// Places named and described in the first of `langs` they are translated to,
+/// every place when `place_ids` is `None`.
+async fn query_localized_places(
+    place_ids: Option<&[i32]>,
+    langs: &[String],
+    db_pool: &Pool<Postgres>,
+) -> Result<Vec<Place>, sqlx::Error> {
+    sqlx::query_as!(
         Place,
-        "SELECT id, name, latitude, longitude, x, y, image_path, description FROM places"
+        "SELECT id, COALESCE(t.name, places.name) as name, latitude, longitude, x, y, image_path,
+         COALESCE(t.description, places.description) as description,
+         (SELECT AVG(rating)::float8 FROM reviews WHERE place_id = places.id AND NOT hidden) as average_rating,
+         (SELECT COUNT(*) FROM reviews WHERE place_id = places.id AND NOT hidden) as review_count
+         FROM places
+         LEFT JOIN LATERAL (
+           SELECT name, description FROM place_translations
+           WHERE place_id = places.id AND lang = ANY($2)
+           ORDER BY array_position($2, lang) LIMIT 1
+         ) t ON true
+         WHERE $1::int4[] IS NULL OR id = ANY($1)",
+        place_ids,
+        langs
     )
-    .fetch_all(&db_pool)
-    .await;
-
-    match query {
-        Ok(vec) => Ok(Places(vec)),
-        Err(err) => Err(err),
-    }
+    .fetch_all(db_pool)
+    .await
 }
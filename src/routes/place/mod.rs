@@ -3,7 +3,7 @@ pub mod slug;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
 pub struct Place {
     pub id: i32,
     pub name: Option<String>,
@@ -13,6 +13,8 @@ pub struct Place {
     pub y: Option<i32>,
     pub image_path: Option<String>,
     pub description: Option<String>,
+    pub average_rating: Option<f64>,
+    pub review_count: Option<i64>,
 }
 
 #[derive(Deserialize, Serialize)]
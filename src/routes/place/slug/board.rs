@@ -0,0 +1,323 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Local, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+
+use super::Slug;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    locale::{preferred_langs, LangParam},
+    place::{get::get_localized_places_by_ids, Place},
+    realtime::{Realtime, RealtimeStore},
+    service_time::ServiceTime,
+    AvailabilityStatus, DatabasePool, Res, Vehicle,
+};
+
+const DEFAULT_LIMIT: i64 = 10;
+const MAX_LIMIT: i64 = 50;
+
+#[derive(Deserialize)]
+pub struct BoardParam {
+    /// Defaults to the current server time.
+    time: Option<NaiveTime>,
+    /// Defaults to the current server date.
+    date: Option<NaiveDate>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BoardEntry {
+    pub schedule_id: i32,
+    pub trip_id: i32,
+    /// Service day of the run, the day before the board's for runs still going
+    /// after midnight and the day after for runs of the next morning.
+    pub service_date: NaiveDate,
+    pub line: i32,
+    pub line_name: Option<String>,
+    pub color: Option<String>,
+    #[serde(rename = "type")]
+    pub vehicle_type: Option<Vehicle>,
+    /// Scheduled departure on the departure board, arrival on the arrival board.
+    /// Counted from the start of the board's day, so it goes past 24:00 for
+    /// runs after midnight.
+    pub time: ServiceTime,
+    /// Seconds the run is predicted late, negative when early.
+    pub delay: i32,
+    /// `time` shifted by `delay`, the board is sorted by it.
+    pub expected_time: ServiceTime,
+    pub status: Option<AvailabilityStatus>,
+    /// Where the run ends on the departure board, where it started on the
+    /// arrival board.
+    pub terminus: Place,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Board {
+    pub place_id: i32,
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    pub entries: Vec<BoardEntry>,
+}
+
+struct BoardRow {
+    schedule_id: i32,
+    trip_id: i32,
+    /// Days from the board's day to the run's service day.
+    day: i32,
+    line: i32,
+    line_name: Option<String>,
+    color: Option<String>,
+    vehicle_type: Option<Vehicle>,
+    time: ServiceTime,
+    expected_time: ServiceTime,
+    status: Option<AvailabilityStatus>,
+    terminus_id: i32,
+}
+
+/// Day, time and size of a board once the defaults are filled in.
+struct BoardWindow {
+    date: NaiveDate,
+    time: NaiveTime,
+    limit: i64,
+}
+
+#[derive(Clone, Copy)]
+enum BoardKind {
+    Departures,
+    Arrivals,
+}
+
+pub async fn get_departures(
+    slug: web::Path<Slug>,
+    search_param: web::Query<SessionToken>,
+    board_param: web::Query<BoardParam>,
+    lang_param: web::Query<LangParam>,
+    http_req: HttpRequest,
+    realtime: web::Data<RealtimeStore>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    board_response(
+        BoardKind::Departures,
+        slug.id,
+        &search_param.token,
+        &board_param,
+        preferred_langs(&http_req, &lang_param),
+        &realtime.snapshot(),
+        &db_pool.pool,
+    )
+    .await
+}
+
+pub async fn get_arrivals(
+    slug: web::Path<Slug>,
+    search_param: web::Query<SessionToken>,
+    board_param: web::Query<BoardParam>,
+    lang_param: web::Query<LangParam>,
+    http_req: HttpRequest,
+    realtime: web::Data<RealtimeStore>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    board_response(
+        BoardKind::Arrivals,
+        slug.id,
+        &search_param.token,
+        &board_param,
+        preferred_langs(&http_req, &lang_param),
+        &realtime.snapshot(),
+        &db_pool.pool,
+    )
+    .await
+}
+
+async fn board_response(
+    kind: BoardKind,
+    place_id: i32,
+    token: &str,
+    board_param: &BoardParam,
+    langs: Vec<String>,
+    realtime: &Realtime,
+    db_pool: &Pool<Postgres>,
+) -> HttpResponse {
+    if let Err(res) = require_session(token, db_pool).await {
+        return res;
+    }
+    if board_param.limit.is_some_and(|limit| limit <= 0) {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "limit must be positive".to_owned(),
+        });
+    }
+
+    let place = sqlx::query_scalar!("SELECT id FROM places WHERE id = $1", place_id)
+        .fetch_optional(db_pool)
+        .await;
+    match place {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(Res {
+                msg: "place not found".to_owned(),
+            })
+        }
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    }
+
+    let now = Local::now();
+    let window = BoardWindow {
+        date: board_param.date.unwrap_or_else(|| now.date_naive()),
+        time: board_param.time.unwrap_or_else(|| now.time()),
+        limit: board_param.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+    };
+    match query_board(kind, place_id, &window, &langs, realtime, db_pool).await {
+        Ok(entries) => HttpResponse::Ok().json(Board {
+            place_id,
+            date: window.date,
+            time: window.time,
+            entries,
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+/// Runs of the board's day still to come, along with the runs of the day
+/// before that are still going after midnight and the runs of the day after
+/// within a day of the board's time. Cancelled runs are left out and
+/// late runs scheduled before the board's time come back in.
+async fn query_board(
+    kind: BoardKind,
+    place_id: i32,
+    window: &BoardWindow,
+    langs: &[String],
+    realtime: &Realtime,
+    db_pool: &Pool<Postgres>,
+) -> Result<Vec<BoardEntry>, sqlx::Error> {
+    // the same leg runs on every service day, each run with its own updates
+    let mut schedule_ids = Vec::new();
+    let mut service_dates = Vec::new();
+    let mut delays = Vec::new();
+    let mut cancelled = Vec::new();
+    for update in realtime.legs.values() {
+        schedule_ids.push(update.schedule_id);
+        service_dates.push(update.service_date);
+        delays.push(match kind {
+            BoardKind::Departures => update.departure_delay,
+            BoardKind::Arrivals => update.arrival_delay,
+        });
+        cancelled.push(update.cancelled);
+    }
+
+    let rows = match kind {
+        BoardKind::Departures => {
+            sqlx::query_as!(
+                BoardRow,
+                "SELECT s.id as schedule_id, s.trip_id, service.day as \"day!\", s.line,
+                 lines.short_name as line_name, lines.color, s.type as \"vehicle_type: Vehicle\",
+                 s.departure_time + service.day * 86400 as \"time!: ServiceTime\",
+                 s.departure_time + service.day * 86400 + COALESCE(rt.delay, 0)
+                   as \"expected_time!: ServiceTime\",
+                 s.status as \"status: AvailabilityStatus\", (
+                   SELECT to_place_id FROM schedules last WHERE last.trip_id = s.trip_id
+                   ORDER BY stop_sequence DESC LIMIT 1
+                 ) as \"terminus_id!\"
+                 FROM schedules s
+                 CROSS JOIN (VALUES (-1), (0), (1)) AS service(day)
+                 JOIN lines ON lines.id = s.line
+                 LEFT JOIN UNNEST($5::int[], $6::date[], $7::int[], $8::bool[])
+                   AS rt(schedule_id, service_date, delay, cancelled)
+                   ON rt.schedule_id = s.id AND rt.service_date = $3::date + service.day
+                 WHERE s.from_place_id = $1
+                 AND s.departure_time + service.day * 86400
+                   BETWEEN $2::int - $4::int AND $2 + 86399
+                 AND s.departure_time + service.day * 86400 + COALESCE(rt.delay, 0) >= $2
+                 AND NOT COALESCE(rt.cancelled, FALSE)
+                 AND trip_runs_on(s.trip_id, $3::date + service.day)
+                 ORDER BY s.departure_time + service.day * 86400 + COALESCE(rt.delay, 0), s.id,
+                 service.day
+                 LIMIT $9",
+                place_id,
+                ServiceTime::from(window.time).seconds(),
+                window.date,
+                realtime.max_delay(),
+                &schedule_ids,
+                &service_dates,
+                &delays,
+                &cancelled,
+                window.limit
+            )
+            .fetch_all(db_pool)
+            .await?
+        }
+        BoardKind::Arrivals => {
+            sqlx::query_as!(
+                BoardRow,
+                "SELECT s.id as schedule_id, s.trip_id, service.day as \"day!\", s.line,
+                 lines.short_name as line_name, lines.color, s.type as \"vehicle_type: Vehicle\",
+                 s.arrival_time + service.day * 86400 as \"time!: ServiceTime\",
+                 s.arrival_time + service.day * 86400 + COALESCE(rt.delay, 0)
+                   as \"expected_time!: ServiceTime\",
+                 s.status as \"status: AvailabilityStatus\", (
+                   SELECT from_place_id FROM schedules first WHERE first.trip_id = s.trip_id
+                   ORDER BY stop_sequence LIMIT 1
+                 ) as \"terminus_id!\"
+                 FROM schedules s
+                 CROSS JOIN (VALUES (-1), (0), (1)) AS service(day)
+                 JOIN lines ON lines.id = s.line
+                 LEFT JOIN UNNEST($5::int[], $6::date[], $7::int[], $8::bool[])
+                   AS rt(schedule_id, service_date, delay, cancelled)
+                   ON rt.schedule_id = s.id AND rt.service_date = $3::date + service.day
+                 WHERE s.to_place_id = $1
+                 AND s.arrival_time + service.day * 86400
+                   BETWEEN $2::int - $4::int AND $2 + 86399
+                 AND s.arrival_time + service.day * 86400 + COALESCE(rt.delay, 0) >= $2
+                 AND NOT COALESCE(rt.cancelled, FALSE)
+                 AND trip_runs_on(s.trip_id, $3::date + service.day)
+                 ORDER BY s.arrival_time + service.day * 86400 + COALESCE(rt.delay, 0), s.id,
+                 service.day
+                 LIMIT $9",
+                place_id,
+                ServiceTime::from(window.time).seconds(),
+                window.date,
+                realtime.max_delay(),
+                &schedule_ids,
+                &service_dates,
+                &delays,
+                &cancelled,
+                window.limit
+            )
+            .fetch_all(db_pool)
+            .await?
+        }
+    };
+
+    let terminus_ids: Vec<i32> = rows.iter().map(|row| row.terminus_id).collect();
+    let places: HashMap<i32, Place> = get_localized_places_by_ids(&terminus_ids, langs, db_pool)
+        .await?
+        .into_iter()
+        .map(|place| (place.id, place))
+        .collect();
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(BoardEntry {
+                terminus: places.get(&row.terminus_id)?.clone(),
+                schedule_id: row.schedule_id,
+                trip_id: row.trip_id,
+                service_date: window.date + Duration::days(row.day.into()),
+                line: row.line,
+                line_name: row.line_name,
+                color: row.color,
+                vehicle_type: row.vehicle_type,
+                time: row.time,
+                delay: row.expected_time.seconds() - row.time.seconds(),
+                expected_time: row.expected_time,
+                status: row.status,
+            })
+        })
+        .collect())
+}
@@ -1,20 +1,24 @@
 use super::Slug;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use sqlx::{error, Pool, Postgres};
 
 use crate::routes::{
     auth::{validate_session_token, SessionToken},
-    place::Place,
+    locale::{preferred_langs, LangParam},
+    place::{get::get_localized_places_by_ids, Place},
     DatabasePool, Res,
 };
 
 pub async fn find_place(
     slug: web::Path<Slug>,
     search_param: web::Query<SessionToken>,
+    lang_param: web::Query<LangParam>,
+    http_req: HttpRequest,
     db_pool: web::Data<DatabasePool>,
 ) -> impl Responder {
     // get place
-    let place = match get_place_by_id(slug.id, &db_pool.pool).await {
+    let langs = preferred_langs(&http_req, &lang_param);
+    let place = match get_localized_place_by_id(slug.id, &langs, &db_pool.pool).await {
         Ok(place) => place,
         Err(err) => match err {
             error::Error::RowNotFound => {
@@ -49,20 +53,13 @@ pub async fn find_place(
     HttpResponse::Ok().json(place)
 }
 
-pub async fn get_place_by_id(
+pub async fn get_localized_place_by_id(
     place_id: i32,
+    langs: &[String],
     db_pool: &Pool<Postgres>,
 ) -> Result<Place, sqlx::Error> {
-    let query = sqlx::query_as!(
-        Place,
-        "SELECT id, name, latitude, longitude, x, y, image_path, description FROM places where id = $1",
-        place_id
-    )
-    .fetch_one(db_pool)
-    .await;
-
-    match query {
-        Ok(query) => Ok(query),
-        Err(err) => Err(err),
-    }
+    get_localized_places_by_ids(&[place_id], langs, db_pool)
+        .await?
+        .pop()
+        .ok_or(sqlx::Error::RowNotFound)
 }
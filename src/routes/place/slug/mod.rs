@@ -1,9 +1,11 @@
 use serde::Deserialize;
 
+pub mod board;
 pub mod delete;
 pub mod get;
+pub mod translation;
 
 #[derive(Deserialize)]
 pub struct Slug {
-    id: i32,
+    pub id: i32,
 }
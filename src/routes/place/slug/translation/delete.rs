@@ -0,0 +1,37 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::TranslationSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn delete_place_translation(
+    slug: web::Path<TranslationSlug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query!(
+        "DELETE FROM place_translations WHERE place_id = $1 AND lang = $2",
+        slug.id,
+        slug.lang.to_lowercase()
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "translation not found".to_owned(),
+        }),
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "translation deleted".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
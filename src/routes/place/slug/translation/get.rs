@@ -0,0 +1,34 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::PlaceTranslation;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    place::slug::Slug,
+    DatabasePool, Res,
+};
+
+pub async fn get_place_translations(
+    slug: web::Path<Slug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query_as!(
+        PlaceTranslation,
+        "SELECT place_id, lang, name, description FROM place_translations
+         WHERE place_id = $1 ORDER BY lang",
+        slug.id
+    )
+    .fetch_all(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(translations) => HttpResponse::Ok().json(translations),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
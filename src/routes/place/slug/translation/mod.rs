@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+pub mod delete;
+pub mod get;
+pub mod put;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct PlaceTranslation {
+    pub place_id: i32,
+    pub lang: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TranslationSlug {
+    id: i32,
+    lang: String,
+}
+
+#[derive(Deserialize)]
+pub struct TranslationRequest {
+    name: Option<String>,
+    description: Option<String>,
+}
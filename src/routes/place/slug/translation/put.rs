@@ -0,0 +1,53 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::{PlaceTranslation, TranslationRequest, TranslationSlug};
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    locale::DEFAULT_LANG,
+    DatabasePool, Res,
+};
+
+pub async fn put_place_translation(
+    slug: web::Path<TranslationSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<TranslationRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let lang = slug.lang.trim().to_lowercase();
+    if lang.is_empty() || lang.len() > 16 || lang == DEFAULT_LANG {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "invalid language".to_owned(),
+        });
+    }
+
+    let query = sqlx::query_as!(
+        PlaceTranslation,
+        "INSERT INTO place_translations (place_id, lang, name, description)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (place_id, lang)
+         DO UPDATE SET name = EXCLUDED.name, description = EXCLUDED.description
+         RETURNING place_id, lang, name, description",
+        slug.id,
+        lang,
+        request.name,
+        request.description
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(translation) => HttpResponse::Ok().json(translation),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(Res {
+                msg: "place not found".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
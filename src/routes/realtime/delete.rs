@@ -0,0 +1,22 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::RealtimeStore;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn delete_realtime(
+    search_param: web::Query<SessionToken>,
+    realtime: web::Data<RealtimeStore>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    realtime.clear();
+    HttpResponse::Ok().json(Res {
+        msg: "realtime updates cleared".to_owned(),
+    })
+}
@@ -0,0 +1,21 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::{LegUpdate, RealtimeStore};
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool,
+};
+
+pub async fn get_realtime(
+    search_param: web::Query<SessionToken>,
+    realtime: web::Data<RealtimeStore>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let mut updates: Vec<LegUpdate> = realtime.snapshot().legs.into_values().collect();
+    updates.sort_by_key(|update| (update.schedule_id, update.service_date));
+    HttpResponse::Ok().json(updates)
+}
@@ -0,0 +1,290 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{gtfs::export::stop_sequences, routes::service_time::ServiceTime};
+
+pub mod delete;
+pub mod get;
+pub mod post;
+
+/// Seconds an update is kept when the feed does not say otherwise.
+pub const DEFAULT_TTL: i64 = 10 * 60;
+pub const MAX_TTL: i64 = 24 * 60 * 60;
+/// Largest delay either way, a run is never predicted a day off its schedule.
+pub const MAX_DELAY: i32 = ServiceTime::DAY;
+
+/// Whether `delay` seconds are within `MAX_DELAY` either way.
+pub fn is_valid_delay(delay: i32) -> bool {
+    (-MAX_DELAY..=MAX_DELAY).contains(&delay)
+}
+
+/// Predicted change to one run of a leg, dropped once `expires_at` has passed.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+pub struct LegUpdate {
+    pub schedule_id: i32,
+    /// Service day of the run, the same leg runs again on the next one.
+    pub service_date: NaiveDate,
+    /// Seconds the leg leaves late, negative when early.
+    pub departure_delay: i32,
+    /// Seconds the leg arrives late, negative when early.
+    pub arrival_delay: i32,
+    pub cancelled: bool,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// When updates given now for `ttl` seconds expire, `None` when out of range.
+pub fn expires_at(ttl: Option<i64>) -> Option<DateTime<Utc>> {
+    let ttl = ttl.unwrap_or(DEFAULT_TTL);
+    (1..=MAX_TTL)
+        .contains(&ttl)
+        .then(|| Utc::now() + Duration::seconds(ttl))
+}
+
+/// Runs of a leg by schedule id and service day.
+type LegRuns = HashMap<(i32, NaiveDate), LegUpdate>;
+
+/// Realtime updates by run, shared by every worker.
+#[derive(Default)]
+pub struct RealtimeStore {
+    legs: RwLock<LegRuns>,
+}
+
+impl RealtimeStore {
+    // a panic while holding the lock leaves whole updates behind, the map is
+    // still usable
+    fn read(&self) -> RwLockReadGuard<'_, LegRuns> {
+        self.legs.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, LegRuns> {
+        self.legs.write().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Replaces the updates of the given runs.
+    pub fn insert(&self, updates: &[LegUpdate]) {
+        let mut legs = self.write();
+        for update in updates {
+            legs.insert((update.schedule_id, update.service_date), *update);
+        }
+    }
+
+    /// Updates that have not expired yet, expired ones are dropped on the way.
+    pub fn snapshot(&self) -> Realtime {
+        let now = Utc::now();
+        if self.read().values().any(|update| update.expires_at <= now) {
+            self.write().retain(|_, update| update.expires_at > now);
+        }
+
+        Realtime {
+            legs: self.read().clone(),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.write().clear();
+    }
+}
+
+/// Updates in force when a request started.
+#[derive(Debug, Default)]
+pub struct Realtime {
+    pub legs: HashMap<(i32, NaiveDate), LegUpdate>,
+}
+
+impl Realtime {
+    pub fn is_cancelled(&self, schedule_id: i32, service_date: NaiveDate) -> bool {
+        self.legs
+            .get(&(schedule_id, service_date))
+            .is_some_and(|update| update.cancelled)
+    }
+
+    pub fn departure_delay(&self, schedule_id: i32, service_date: NaiveDate) -> i32 {
+        self.legs
+            .get(&(schedule_id, service_date))
+            .map_or(0, |update| update.departure_delay)
+    }
+
+    pub fn arrival_delay(&self, schedule_id: i32, service_date: NaiveDate) -> i32 {
+        self.legs
+            .get(&(schedule_id, service_date))
+            .map_or(0, |update| update.arrival_delay)
+    }
+
+    /// Longest delay of any leg, how much earlier than asked a scheduled time
+    /// may be and still be predicted in time.
+    pub fn max_delay(&self) -> i32 {
+        self.legs
+            .values()
+            .map(|update| update.departure_delay.max(update.arrival_delay))
+            .max()
+            .unwrap_or(0)
+            .max(0)
+    }
+}
+
+/// Update of a whole trip, modelled after a GTFS-Realtime TripUpdate.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TripUpdate {
+    pub trip_id: i32,
+    /// Service day of the run updated, defaults to the current server date.
+    pub service_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Delay of the stops before the first stop update.
+    pub delay: Option<i32>,
+    #[serde(default)]
+    pub stops: Vec<StopUpdate>,
+}
+
+/// A delay holds for the following stops until another stop update says
+/// otherwise. A skipped stop cancels the legs arriving at and leaving it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StopUpdate {
+    /// Stop of the trip by its `stop_sequence` in the exported
+    /// `stop_times.txt`, the one of the imported feed for imported trips.
+    pub stop_sequence: i32,
+    pub arrival_delay: Option<i32>,
+    pub departure_delay: Option<i32>,
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScheduleUpdate {
+    pub schedule_id: i32,
+    /// Service day of the run updated, defaults to the current server date.
+    pub service_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub delay: i32,
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+impl TripUpdate {
+    /// Spreads the update over the legs of the trip, running on `service_date`
+    /// unless the update says otherwise. Fails with what is wrong with the update.
+    pub fn leg_updates(
+        &self,
+        legs: &TripLegs,
+        service_date: NaiveDate,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Vec<LegUpdate>, String> {
+        let delays = self
+            .stops
+            .iter()
+            .flat_map(|stop| [stop.arrival_delay, stop.departure_delay]);
+        if !delays.chain([self.delay]).flatten().all(is_valid_delay) {
+            return Err(format!(
+                "delays of trip {} must be between -{} and {}",
+                self.trip_id, MAX_DELAY, MAX_DELAY
+            ));
+        }
+
+        let stop_count = legs.stop_sequences.len();
+        let mut stops: Vec<(usize, &StopUpdate)> = Vec::new();
+        for stop in &self.stops {
+            match legs
+                .stop_sequences
+                .iter()
+                .position(|&known| known == stop.stop_sequence)
+            {
+                Some(position) => stops.push((position, stop)),
+                None => {
+                    return Err(format!(
+                        "stop_sequence {} is not a stop of trip {}",
+                        stop.stop_sequence, self.trip_id
+                    ))
+                }
+            }
+        }
+        stops.sort_by_key(|(position, _)| *position);
+        if let Some(pair) = stops.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!(
+                "stop_sequence {} is updated twice",
+                pair[1].1.stop_sequence
+            ));
+        }
+
+        let service_date = self.service_date.unwrap_or(service_date);
+        // delays at each stop of the trip, carried over from the previous stop
+        let mut arrivals = vec![None; stop_count];
+        let mut departures = vec![None; stop_count];
+        let mut skipped = vec![false; stop_count];
+        let mut carried = self.delay;
+        let mut next = stops.iter().peekable();
+        for i in 0..stop_count {
+            if let Some((_, stop)) = next.next_if(|(position, _)| *position == i) {
+                let arrival = stop.arrival_delay.or(stop.departure_delay).or(carried);
+                carried = stop.departure_delay.or(arrival);
+                arrivals[i] = arrival;
+                skipped[i] = stop.skipped;
+            } else {
+                arrivals[i] = carried;
+            }
+            departures[i] = carried;
+        }
+
+        Ok(legs
+            .schedule_ids
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &schedule_id)| {
+                let cancelled = self.cancelled || skipped[i] || skipped[i + 1];
+                let (departure_delay, arrival_delay) = (departures[i], arrivals[i + 1]);
+                (cancelled || departure_delay.is_some() || arrival_delay.is_some()).then_some(
+                    LegUpdate {
+                        schedule_id,
+                        service_date,
+                        departure_delay: departure_delay.unwrap_or(0),
+                        arrival_delay: arrival_delay.unwrap_or(0),
+                        cancelled,
+                        expires_at,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+/// Legs of a trip in travel order, with the `stop_sequence` of its stops.
+#[derive(Debug, Default)]
+pub struct TripLegs {
+    pub schedule_ids: Vec<i32>,
+    /// One more than the legs, see `gtfs::export::stop_sequences`.
+    pub stop_sequences: Vec<i32>,
+}
+
+/// Legs of each trip, the trips without legs left out.
+pub async fn get_trip_legs(
+    trip_ids: &[i32],
+    db_pool: &Pool<Postgres>,
+) -> Result<HashMap<i32, TripLegs>, sqlx::Error> {
+    let legs = sqlx::query!(
+        "SELECT id, trip_id, gtfs_stop_sequence FROM schedules WHERE trip_id = ANY($1)
+         ORDER BY trip_id, stop_sequence",
+        trip_ids
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut trip_legs: HashMap<i32, (Vec<i32>, Vec<Option<i32>>)> = HashMap::new();
+    for leg in legs {
+        let (schedule_ids, gtfs_stop_sequences) = trip_legs.entry(leg.trip_id).or_default();
+        schedule_ids.push(leg.id);
+        gtfs_stop_sequences.push(leg.gtfs_stop_sequence);
+    }
+
+    Ok(trip_legs
+        .into_iter()
+        .map(|(trip_id, (schedule_ids, gtfs_stop_sequences))| {
+            let legs = TripLegs {
+                schedule_ids,
+                stop_sequences: stop_sequences(&gtfs_stop_sequences),
+            };
+            (trip_id, legs)
+        })
+        .collect())
+}
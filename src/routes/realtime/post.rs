@@ -0,0 +1,121 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{Local, NaiveDate};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    expires_at, get_trip_legs, is_valid_delay, LegUpdate, RealtimeStore, ScheduleUpdate,
+    TripUpdate, MAX_DELAY, MAX_TTL,
+};
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    DatabasePool, Res,
+};
+
+#[derive(Deserialize)]
+pub struct RealtimeRequest {
+    /// Seconds the updates are kept, defaults to `DEFAULT_TTL`.
+    pub ttl: Option<i64>,
+    #[serde(default)]
+    pub trips: Vec<TripUpdate>,
+    /// Applied after the trip updates, so they win over them.
+    #[serde(default)]
+    pub schedules: Vec<ScheduleUpdate>,
+}
+
+pub async fn post_realtime(
+    search_param: web::Query<SessionToken>,
+    request: web::Json<RealtimeRequest>,
+    realtime: web::Data<RealtimeStore>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let expires_at = match expires_at(request.ttl) {
+        Some(expires_at) => expires_at,
+        None => {
+            return HttpResponse::BadRequest().json(Res {
+                msg: format!("ttl must be between 1 and {}", MAX_TTL),
+            })
+        }
+    };
+    if request.trips.is_empty() && request.schedules.is_empty() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "give at least one trip or schedule update".to_owned(),
+        });
+    }
+
+    let trip_ids: Vec<i32> = request.trips.iter().map(|trip| trip.trip_id).collect();
+    let schedule_ids: Vec<i32> = request
+        .schedules
+        .iter()
+        .map(|schedule| schedule.schedule_id)
+        .collect();
+    let trip_legs = get_trip_legs(&trip_ids, &db_pool.pool).await;
+    let known_schedules =
+        sqlx::query_scalar!("SELECT id FROM schedules WHERE id = ANY($1)", &schedule_ids)
+            .fetch_all(&db_pool.pool)
+            .await;
+    let (trip_legs, known_schedules) = match (trip_legs, known_schedules) {
+        (Ok(trip_legs), Ok(known_schedules)) => (
+            trip_legs,
+            known_schedules.into_iter().collect::<HashSet<i32>>(),
+        ),
+        _ => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    };
+
+    let today = Local::now().date_naive();
+    let mut updates: Vec<LegUpdate> = Vec::new();
+    for trip in &request.trips {
+        let legs = match trip_legs.get(&trip.trip_id) {
+            Some(legs) => legs,
+            None => {
+                return HttpResponse::BadRequest().json(Res {
+                    msg: format!("unknown trip {}", trip.trip_id),
+                })
+            }
+        };
+        match trip.leg_updates(legs, today, expires_at) {
+            Ok(leg_updates) => updates.extend(leg_updates),
+            Err(msg) => return HttpResponse::BadRequest().json(Res { msg }),
+        }
+    }
+    for schedule in &request.schedules {
+        if !is_valid_delay(schedule.delay) {
+            return HttpResponse::BadRequest().json(Res {
+                msg: format!("delay must be between -{} and {}", MAX_DELAY, MAX_DELAY),
+            });
+        }
+        if !known_schedules.contains(&schedule.schedule_id) {
+            return HttpResponse::BadRequest().json(Res {
+                msg: format!("unknown schedule {}", schedule.schedule_id),
+            });
+        }
+        updates.push(LegUpdate {
+            schedule_id: schedule.schedule_id,
+            service_date: schedule.service_date.unwrap_or(today),
+            departure_delay: schedule.delay,
+            arrival_delay: schedule.delay,
+            cancelled: schedule.cancelled,
+            expires_at,
+        });
+    }
+
+    realtime.insert(&updates);
+
+    // a run updated twice keeps its last update, as in the store
+    let mut updates: Vec<LegUpdate> = updates
+        .into_iter()
+        .map(|update| ((update.schedule_id, update.service_date), update))
+        .collect::<HashMap<(i32, NaiveDate), LegUpdate>>()
+        .into_values()
+        .collect();
+    updates.sort_by_key(|update| (update.schedule_id, update.service_date));
+    HttpResponse::Ok().json(updates)
+}
@@ -0,0 +1,68 @@
+use actix_web::{web, HttpResponse, Responder};
+use sqlx::{Pool, Postgres};
+
+use super::{Review, Reviews};
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    place::slug::Slug,
+    DatabasePool, Pagination, Res, Role,
+};
+
+pub async fn get_place_reviews(
+    slug: web::Path<Slug>,
+    search_param: web::Query<SessionToken>,
+    pagination: web::Query<Pagination>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    // admins need to see hidden reviews to be able to restore them
+    let include_hidden = matches!(user.role, Role::ADMIN);
+    match query_reviews(slug.id, include_hidden, &pagination, &db_pool.pool).await {
+        Ok(reviews) => HttpResponse::Ok().json(reviews),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+async fn query_reviews(
+    place_id: i32,
+    include_hidden: bool,
+    pagination: &Pagination,
+    db_pool: &Pool<Postgres>,
+) -> Result<Reviews, sqlx::Error> {
+    let reviews = sqlx::query_as!(
+        Review,
+        "SELECT id, place_id, username, rating, text, hidden, created_at, updated_at
+         FROM reviews
+         WHERE place_id = $1 AND (NOT hidden OR $2)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3 OFFSET $4",
+        place_id,
+        include_hidden,
+        pagination.per_page(),
+        pagination.offset()
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let total = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM reviews WHERE place_id = $1 AND (NOT hidden OR $2)",
+        place_id,
+        include_hidden
+    )
+    .fetch_one(db_pool)
+    .await?
+    .unwrap_or(0);
+
+    Ok(Reviews {
+        reviews,
+        page: pagination.page(),
+        per_page: pagination.per_page(),
+        total,
+    })
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub mod get;
+pub mod post;
+pub mod slug;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Review {
+    pub id: i32,
+    pub place_id: i32,
+    pub username: String,
+    pub rating: i16,
+    pub text: Option<String>,
+    pub hidden: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Reviews {
+    pub reviews: Vec<Review>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewRequest {
+    pub rating: i16,
+    pub text: Option<String>,
+}
+
+impl ReviewRequest {
+    fn is_valid(&self) -> bool {
+        (1..=5).contains(&self.rating)
+    }
+}
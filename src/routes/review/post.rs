@@ -0,0 +1,54 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::{Review, ReviewRequest};
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    place::slug::Slug,
+    DatabasePool, Res,
+};
+
+pub async fn post_place_review(
+    slug: web::Path<Slug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<ReviewRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "rating must be between 1 and 5".to_owned(),
+        });
+    }
+
+    let query = sqlx::query_as!(
+        Review,
+        "INSERT INTO reviews (place_id, username, rating, text) VALUES ($1, $2, $3, $4)
+         RETURNING id, place_id, username, rating, text, hidden, created_at, updated_at",
+        slug.id,
+        user.username,
+        request.rating,
+        request.text
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(review) => HttpResponse::Created().json(review),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => HttpResponse::Conflict()
+            .json(Res {
+                msg: "place already reviewed".to_owned(),
+            }),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(Res {
+                msg: "place not found".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
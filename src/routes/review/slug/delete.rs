@@ -0,0 +1,38 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::ReviewSlug;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn delete_review(
+    slug: web::Path<ReviewSlug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    let query = sqlx::query!(
+        "DELETE FROM reviews WHERE id = $1 AND username = $2",
+        slug.review_id,
+        user.username
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "review not found".to_owned(),
+        }),
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "review deleted".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
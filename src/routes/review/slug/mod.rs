@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+pub mod delete;
+pub mod put;
+pub mod visibility;
+
+#[derive(Deserialize)]
+pub struct ReviewSlug {
+    review_id: i32,
+}
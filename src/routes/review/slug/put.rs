@@ -0,0 +1,49 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::ReviewSlug;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    review::{Review, ReviewRequest},
+    DatabasePool, Res,
+};
+
+pub async fn put_review(
+    slug: web::Path<ReviewSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<ReviewRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "rating must be between 1 and 5".to_owned(),
+        });
+    }
+
+    let query = sqlx::query_as!(
+        Review,
+        "UPDATE reviews SET rating = $3, text = $4, updated_at = now()
+         WHERE id = $1 AND username = $2
+         RETURNING id, place_id, username, rating, text, hidden, created_at, updated_at",
+        slug.review_id,
+        user.username,
+        request.rating,
+        request.text
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(review) => HttpResponse::Ok().json(review),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(Res {
+            msg: "review not found".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
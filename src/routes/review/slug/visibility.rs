@@ -0,0 +1,45 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use super::ReviewSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    review::Review,
+    DatabasePool, Res,
+};
+
+#[derive(Deserialize)]
+pub struct VisibilityRequest {
+    hidden: bool,
+}
+
+pub async fn put_review_visibility(
+    slug: web::Path<ReviewSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<VisibilityRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query_as!(
+        Review,
+        "UPDATE reviews SET hidden = $2 WHERE id = $1
+         RETURNING id, place_id, username, rating, text, hidden, created_at, updated_at",
+        slug.review_id,
+        request.hidden
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(review) => HttpResponse::Ok().json(review),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(Res {
+            msg: "review not found".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,32 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn delete_route_history(
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    let query = sqlx::query!(
+        "DELETE FROM route_searches WHERE username = $1",
+        user.username
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "history cleared".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
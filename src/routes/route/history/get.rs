@@ -0,0 +1,61 @@
+use actix_web::{web, HttpResponse, Responder};
+use sqlx::{Pool, Postgres};
+
+use super::{RouteSearch, RouteSearches};
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Pagination, Res,
+};
+
+pub async fn get_route_history(
+    search_param: web::Query<SessionToken>,
+    pagination: web::Query<Pagination>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    match query_route_history(&user.username, &pagination, &db_pool.pool).await {
+        Ok(searches) => HttpResponse::Ok().json(searches),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+async fn query_route_history(
+    username: &str,
+    pagination: &Pagination,
+    db_pool: &Pool<Postgres>,
+) -> Result<RouteSearches, sqlx::Error> {
+    let searches = sqlx::query_as!(
+        RouteSearch,
+        "SELECT id, from_place_id, to_place_id, departure_time, arrive_by, path_count, best_travel_seconds, searched_at
+         FROM route_searches
+         WHERE username = $1
+         ORDER BY searched_at DESC, id DESC
+         LIMIT $2 OFFSET $3",
+        username,
+        pagination.per_page(),
+        pagination.offset()
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let total = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM route_searches WHERE username = $1",
+        username
+    )
+    .fetch_one(db_pool)
+    .await?
+    .unwrap_or(0);
+
+    Ok(RouteSearches {
+        searches,
+        page: pagination.page(),
+        per_page: pagination.per_page(),
+        total,
+    })
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+
+pub mod delete;
+pub mod get;
+pub mod popular;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct RouteSearch {
+    pub id: i32,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub departure_time: NaiveTime,
+    /// Whether `departure_time` is the time to arrive by.
+    pub arrive_by: bool,
+    pub path_count: i32,
+    /// Seconds the best path takes, counted as the search counted them.
+    pub best_travel_seconds: Option<i32>,
+    pub searched_at: DateTime<Utc>,
+}
+
+/// A route search as recorded for a user.
+#[derive(Debug)]
+pub struct NewRouteSearch {
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub departure_time: NaiveTime,
+    pub arrive_by: bool,
+    pub path_count: i32,
+    pub best_travel_seconds: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RouteSearches {
+    pub searches: Vec<RouteSearch>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+pub async fn record_route_search(
+    username: &str,
+    search: &NewRouteSearch,
+    db_pool: &Pool<Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO route_searches
+         (username, from_place_id, to_place_id, departure_time, arrive_by, path_count, best_travel_seconds)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        username,
+        search.from_place_id,
+        search.to_place_id,
+        search.departure_time,
+        search.arrive_by,
+        search.path_count,
+        search.best_travel_seconds
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
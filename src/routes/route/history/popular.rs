@@ -0,0 +1,54 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    DatabasePool, Res,
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PopularRoute {
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub search_count: i64,
+    pub user_count: i64,
+    pub last_searched_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct PopularParam {
+    limit: Option<i64>,
+}
+
+pub async fn get_popular_routes(
+    search_param: web::Query<SessionToken>,
+    popular_param: web::Query<PopularParam>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query_as!(
+        PopularRoute,
+        "SELECT from_place_id, to_place_id,
+         COUNT(*) as \"search_count!\",
+         COUNT(DISTINCT username) as \"user_count!\",
+         MAX(searched_at) as \"last_searched_at!\"
+         FROM route_searches
+         GROUP BY from_place_id, to_place_id
+         ORDER BY 3 DESC, 5 DESC
+         LIMIT $1",
+        popular_param.limit.unwrap_or(10).clamp(1, 100)
+    )
+    .fetch_all(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(routes) => HttpResponse::Ok().json(routes),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use super::{
+    graph::{GraphLeg, Weight},
+    planner::{Journey, JourneyPlanner, JourneyQuery},
+};
+use crate::routes::transfer::TransferRules;
+
+/// Search scanning every leg once in departure order, the connection scan
+/// algorithm. Places are told apart by the line they are reached on, as the
+/// time needed to change trips depends on it. Arriving by a time, legs are
+/// scanned backwards in arrival order from the destination instead.
+#[derive(Debug)]
+pub struct ConnectionScan<'a> {
+    /// Legs ordered by departure, then arrival.
+    connections: Vec<GraphLeg>,
+    /// Positions in `connections` ordered by arrival, then departure.
+    arrivals: Vec<usize>,
+    transfers: &'a TransferRules,
+}
+
+impl<'a> ConnectionScan<'a> {
+    pub fn new(mut legs: Vec<GraphLeg>, transfers: &'a TransferRules) -> Self {
+        legs.sort_by_key(|leg| {
+            (
+                leg.departure_time,
+                leg.arrival_time,
+                leg.trip_id,
+                leg.stop_sequence,
+            )
+        });
+        let mut arrivals: Vec<usize> = (0..legs.len()).collect();
+        arrivals.sort_by_key(|&i| (legs[i].arrival_time, legs[i].departure_time));
+        Self {
+            connections: legs,
+            arrivals,
+            transfers,
+        }
+    }
+}
+
+/// Riding the trip of a boarding up to connection `exit`. Scanning
+/// backwards, trips are boarded where they are left and ridden back to `exit`.
+#[derive(Debug, Clone, Copy)]
+struct Reach {
+    boarding: usize,
+    exit: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Boarding {
+    /// Ride the trip was changed from, `None` when boarded at the origin.
+    /// Scanning backwards, the ride changed to, `None` when left at the
+    /// destination.
+    prev: Option<Reach>,
+    transfers: usize,
+}
+
+/// Way found to a place arriving on `line`, on none at the origin. Scanning
+/// backwards, way found from a place leaving on `line`, on none at the
+/// destination.
+#[derive(Debug, Clone, Copy)]
+struct Label {
+    line: Option<i32>,
+    /// When the place is reached, or left when scanning backwards.
+    time: i32,
+    transfers: usize,
+    reach: Option<Reach>,
+}
+
+/// What a scan keeps track of besides its labels.
+#[derive(Debug)]
+struct Rides {
+    boardings: Vec<Boarding>,
+    /// How far every trip boarded has been ridden.
+    trips: HashMap<i32, Reach>,
+    /// Connection ridden before each one on the same trip, after it when
+    /// scanning backwards.
+    prev_connections: Vec<Option<usize>>,
+}
+
+impl Rides {
+    fn new(connections: usize) -> Self {
+        Self {
+            boardings: Vec::new(),
+            trips: HashMap::new(),
+            prev_connections: vec![None; connections],
+        }
+    }
+
+    /// Rides connection `i`, staying on its trip from `stay` unless boarding
+    /// it from `board` changes trips less often.
+    fn ride(
+        &mut self,
+        i: usize,
+        trip_id: i32,
+        stay: Option<Reach>,
+        board: Option<(Option<Reach>, usize)>,
+    ) -> Option<Reach> {
+        let reach = match (stay, board) {
+            (Some(stay), board)
+                if board.is_none_or(|(_, transfers)| {
+                    self.boardings[stay.boarding].transfers <= transfers
+                }) =>
+            {
+                self.prev_connections[i] = Some(stay.exit);
+                Reach {
+                    boarding: stay.boarding,
+                    exit: i,
+                }
+            }
+            (_, Some((prev, transfers))) => {
+                self.boardings.push(Boarding { prev, transfers });
+                Reach {
+                    boarding: self.boardings.len() - 1,
+                    exit: i,
+                }
+            }
+            _ => return None,
+        };
+        self.trips.insert(trip_id, reach);
+
+        Some(reach)
+    }
+
+    /// Connections ridden to get to `reach`, walking back ride by ride.
+    fn ridden(&self, mut reach: Option<Reach>) -> Vec<usize> {
+        let mut connections = Vec::new();
+        while let Some(Reach { boarding, exit }) = reach {
+            let mut connection = Some(exit);
+            while let Some(i) = connection {
+                connections.push(i);
+                connection = self.prev_connections[i];
+            }
+            reach = self.boardings[boarding].prev;
+        }
+        connections
+    }
+}
+
+/// Keeps `label` among the labels of its place unless one on the same line is
+/// as good on both time and transfers, `later` telling which time is better.
+fn add_label(place_labels: &mut Vec<Label>, label: Label, later: bool) {
+    let beats = |a: &Label, b: &Label| {
+        a.line == b.line
+            && a.transfers <= b.transfers
+            && if later {
+                a.time >= b.time
+            } else {
+                a.time <= b.time
+            }
+    };
+    if place_labels.iter().any(|known| beats(known, &label)) {
+        return;
+    }
+    place_labels.retain(|known| !beats(&label, known));
+    place_labels.push(label);
+}
+
+impl ConnectionScan<'_> {
+    /// Earliest arrivals from `query.time`.
+    fn scan_forward(&self, query: &JourneyQuery) -> Option<Vec<Journey>> {
+        let departure = query.time.seconds();
+        // every place keeps, for each line, the labels no other one beats on
+        // both arrival and transfers
+        let mut labels = HashMap::from([(
+            query.origin_place_id,
+            vec![Label {
+                line: None,
+                time: departure,
+                transfers: 0,
+                reach: None,
+            }],
+        )]);
+        let mut rides = Rides::new(self.connections.len());
+
+        let first = self
+            .connections
+            .partition_point(|connection| connection.departure_time < query.time);
+        for (i, connection) in self.connections.iter().enumerate().skip(first) {
+            let direct = labels
+                .get(&query.destination_place_id)
+                .into_iter()
+                .flatten()
+                .any(|label| {
+                    label.reach.is_some()
+                        && label.transfers == 0
+                        && label.time <= connection.departure_time.seconds()
+                });
+            if direct {
+                // nothing leaving from now on arrives earlier or changes less
+                break;
+            }
+
+            // staying on requires the trip to carry on from where it stopped
+            let stay = rides
+                .trips
+                .get(&connection.trip_id)
+                .copied()
+                .filter(|reach| {
+                    let last = &self.connections[reach.exit];
+                    last.to_place_id == connection.from_place_id
+                        && last.stop_sequence < connection.stop_sequence
+                });
+            let board = labels
+                .get(&connection.from_place_id)
+                .into_iter()
+                .flatten()
+                .filter(|label| {
+                    let transfer = label.line.map_or(0, |line| {
+                        self.transfers
+                            .min_transfer(connection.from_place_id, line, connection.line)
+                    });
+                    label.time + transfer <= connection.departure_time.seconds()
+                })
+                .map(|label| {
+                    (
+                        label.reach,
+                        label.transfers + usize::from(label.line.is_some()),
+                    )
+                })
+                .filter(|(_, transfers)| query.allows(*transfers))
+                .min_by_key(|(_, transfers)| *transfers);
+            let Some(reach) = rides.ride(i, connection.trip_id, stay, board) else {
+                continue;
+            };
+
+            let label = Label {
+                line: Some(connection.line),
+                time: connection.arrival_time.seconds(),
+                transfers: rides.boardings[reach.boarding].transfers,
+                reach: Some(reach),
+            };
+            add_label(
+                labels.entry(connection.to_place_id).or_default(),
+                label,
+                false,
+            );
+        }
+
+        let journeys = labels
+            .get(&query.destination_place_id)?
+            .iter()
+            .filter_map(|label| {
+                let travel_seconds = Weight::try_from(label.time - departure).ok()?;
+                // the legs come out last first
+                let ids: Vec<usize> = rides
+                    .ridden(label.reach)
+                    .into_iter()
+                    .rev()
+                    .map(|i| self.connections[i].id as usize)
+                    .collect();
+                (!ids.is_empty()).then_some(Journey {
+                    schedule_ids: ids,
+                    travel_seconds,
+                    transfers: label.transfers,
+                })
+            })
+            .collect();
+
+        query.pareto_set(journeys)
+    }
+
+    /// Latest departures still arriving by `query.time`, the forward scan run
+    /// from the destination with trips ridden backwards.
+    fn scan_backward(&self, query: &JourneyQuery) -> Option<Vec<Journey>> {
+        let arrival = query.time.seconds();
+        // every place keeps, for each line, the labels no other one beats on
+        // both departure and transfers
+        let mut labels = HashMap::from([(
+            query.destination_place_id,
+            vec![Label {
+                line: None,
+                time: arrival,
+                transfers: 0,
+                reach: None,
+            }],
+        )]);
+        let mut rides = Rides::new(self.connections.len());
+
+        let last = self
+            .arrivals
+            .partition_point(|&i| self.connections[i].arrival_time <= query.time);
+        for &i in self.arrivals[..last].iter().rev() {
+            let connection = &self.connections[i];
+            let direct = labels
+                .get(&query.origin_place_id)
+                .into_iter()
+                .flatten()
+                .any(|label| {
+                    label.reach.is_some()
+                        && label.transfers == 0
+                        && label.time >= connection.arrival_time.seconds()
+                });
+            if direct {
+                // nothing arriving from now on leaves later or changes less
+                break;
+            }
+
+            // staying on requires the trip to have come from where it went on
+            let stay = rides
+                .trips
+                .get(&connection.trip_id)
+                .copied()
+                .filter(|reach| {
+                    let next = &self.connections[reach.exit];
+                    next.from_place_id == connection.to_place_id
+                        && next.stop_sequence > connection.stop_sequence
+                });
+            let board = labels
+                .get(&connection.to_place_id)
+                .into_iter()
+                .flatten()
+                .filter(|label| {
+                    let transfer = label.line.map_or(0, |line| {
+                        self.transfers
+                            .min_transfer(connection.to_place_id, connection.line, line)
+                    });
+                    connection.arrival_time.seconds() + transfer <= label.time
+                })
+                .map(|label| {
+                    (
+                        label.reach,
+                        label.transfers + usize::from(label.line.is_some()),
+                    )
+                })
+                .filter(|(_, transfers)| query.allows(*transfers))
+                .min_by_key(|(_, transfers)| *transfers);
+            let Some(reach) = rides.ride(i, connection.trip_id, stay, board) else {
+                continue;
+            };
+
+            let label = Label {
+                line: Some(connection.line),
+                time: connection.departure_time.seconds(),
+                transfers: rides.boardings[reach.boarding].transfers,
+                reach: Some(reach),
+            };
+            add_label(
+                labels.entry(connection.from_place_id).or_default(),
+                label,
+                true,
+            );
+        }
+
+        let journeys = labels
+            .get(&query.origin_place_id)?
+            .iter()
+            .filter_map(|label| {
+                let travel_seconds = Weight::try_from(arrival - label.time).ok()?;
+                // walking back from the origin, the legs come out in order
+                let ids: Vec<usize> = rides
+                    .ridden(label.reach)
+                    .into_iter()
+                    .map(|i| self.connections[i].id as usize)
+                    .collect();
+                (!ids.is_empty()).then_some(Journey {
+                    schedule_ids: ids,
+                    travel_seconds,
+                    transfers: label.transfers,
+                })
+            })
+            .collect();
+
+        query.pareto_set(journeys)
+    }
+}
+
+impl JourneyPlanner for ConnectionScan<'_> {
+    fn plan(&self, query: &JourneyQuery) -> Option<Vec<Journey>> {
+        if query.arrive_by {
+            self.scan_backward(query)
+        } else {
+            self.scan_forward(query)
+        }
+    }
+}
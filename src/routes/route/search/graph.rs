@@ -1,241 +1,225 @@
 use anyhow::{anyhow, Result};
-use chrono::NaiveTime;
-use sqlx::{query, PgPool};
-use std::cmp::Ordering;
+use chrono::{Duration, NaiveDate};
 use std::collections::HashMap;
-use std::ptr::NonNull;
-use std::str::FromStr;
+
+use super::timetable::Timetable;
+use crate::routes::{
+    realtime::Realtime, service_time::ServiceTime, transfer::TransferRules, AvailabilityStatus,
+    Vehicle,
+};
 
 type SchedId = usize;
-type ToPlaceId = usize;
-type Weight = usize;
-type NodeMap = HashMap<SchedId, *mut Node>;
+/// Position of a node in `Graph::nodes`.
+pub type NodeIndex = usize;
+/// Seconds since the departure time of a search.
+pub type Weight = usize;
+
+/// A leg as searched, its times already moved to the day it is searched on
+/// and predicted by realtime updates.
+#[derive(Debug, Clone)]
+pub struct GraphLeg {
+    pub id: i32,
+    pub trip_id: i32,
+    pub line: i32,
+    pub stop_sequence: i32,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub departure_time: ServiceTime,
+    pub arrival_time: ServiceTime,
+}
 
 #[derive(Debug)]
 pub struct Node {
-    pub id: usize,
+    pub id: SchedId,
+    pub trip_id: i32,
     pub from_place_id: usize,
     pub to_place_id: usize,
-    pub departure_time: String,
-    pub arrival_time: String,
-    pub prev_node: Option<NonNull<Node>>,
-    pub edges: HashMap<NonNull<Node>, Weight>,
-    pub weight: Option<usize>,
-}
-
-impl PartialEq for Node {
-    fn eq(&self, other: &Self) -> bool {
-        self.weight == other.weight
-    }
+    pub departure_time: ServiceTime,
+    pub arrival_time: ServiceTime,
+    pub edges: Vec<Edge>,
 }
 
-impl Eq for Node {}
-
-impl PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.weight.partial_cmp(&other.weight)
-    }
-}
-
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.weight.cmp(&other.weight)
-    }
+/// Ride from the place a node leaves from on `leg`, which reaches `node`.
+#[derive(Debug, Clone, Copy)]
+pub struct Edge {
+    pub node: NodeIndex,
+    pub leg: NodeIndex,
+    /// When the place `node` leaves from is reached.
+    pub arrival: Weight,
 }
 
-impl Node {
-    fn new(
-        id: usize,
-        from_place_id: usize,
-        to_place_id: usize,
-        departure_time: String,
-        arrival_time: String,
-    ) -> Self {
-        Self {
-            id,
-            from_place_id,
-            to_place_id,
-            departure_time,
-            arrival_time,
-            prev_node: None,
-            edges: HashMap::new(),
-            weight: None,
-        }
-    }
-}
-
-#[derive(Debug)]
+/// Legs connected to the legs that can be taken after them. The graph is not
+/// changed by searches, so one graph can serve many of them.
+#[derive(Debug, Default)]
 pub struct Graph {
-    pub nodes: NodeMap,
+    pub nodes: Vec<Node>,
+    index: HashMap<SchedId, NodeIndex>,
 }
 
 impl Graph {
-    pub async unsafe fn new(db_pool: PgPool, departure_time: &str) -> Result<Self> {
-        let mut nodes: NodeMap = init_nodes(db_pool.clone(), departure_time).await?;
-        connect_edges(db_pool, departure_time, &mut nodes).await?;
-        Ok(Self { nodes })
+    /// Graph of the legs of `timetable` that are available, leave within a
+    /// day of `departure_time` on `date` and, when `vehicle` is set, run by
+    /// that vehicle. Node times count from the start of `date`, so a leg of the
+    /// next morning leaves after 24:00. Times are the ones predicted by
+    /// `realtime`, cancelled legs are left out. Trips are changed as the
+    /// transfer rules of `timetable` allow.
+    pub fn new(
+        timetable: &Timetable,
+        departure_time: ServiceTime,
+        date: NaiveDate,
+        vehicle: Option<Vehicle>,
+        realtime: &Realtime,
+    ) -> Result<Self> {
+        let legs = get_graph_legs(timetable, departure_time, date, vehicle, realtime);
+        Self::from_legs(legs, departure_time, &timetable.transfers)
     }
-}
 
-impl Drop for Graph {
-    fn drop(&mut self) {
-        for (_, node) in self.nodes.iter() {
-            unsafe {
-                let _ = Box::from_raw(*node);
-            }
+    /// Connects every leg to the legs reachable from it. A leg leads to the
+    /// next leg of its trip, and to the next leg of any trip leaving its origin
+    /// late enough to change to after arriving with the leg before, the edge
+    /// weighing the seconds from `departure_time` to the arrival of the leg
+    /// before.
+    pub fn from_legs(
+        mut legs: Vec<GraphLeg>,
+        departure_time: ServiceTime,
+        transfers: &TransferRules,
+    ) -> Result<Self> {
+        legs.sort_by_key(|leg| (leg.trip_id, leg.stop_sequence));
+        let index: HashMap<SchedId, NodeIndex> = legs
+            .iter()
+            .enumerate()
+            .map(|(i, leg)| (leg.id as SchedId, i))
+            .collect();
+
+        // the vehicle serving a leg carries on with the next leg of its trip
+        let next_legs: Vec<Option<NodeIndex>> = (0..legs.len())
+            .map(|i| {
+                legs.get(i + 1)
+                    .filter(|next| next.trip_id == legs[i].trip_id)
+                    .filter(|next| next.from_place_id == legs[i].to_place_id)
+                    .map(|_| i + 1)
+            })
+            .collect();
+
+        let mut departures: HashMap<i32, Vec<NodeIndex>> = HashMap::new();
+        for (i, leg) in legs.iter().enumerate() {
+            departures.entry(leg.from_place_id).or_default().push(i);
         }
-    }
-}
 
-async unsafe fn get_node_edges(db_pool: PgPool, node: &*mut Node) -> Result<Vec<SchedId>> {
-    let node_departure_time = NaiveTime::from_str(&(*(*node)).departure_time)?;
-    let from_place_sched_id = (*(*node)).from_place_id;
-    let sched_destinations: Vec<(SchedId, ToPlaceId)> = query!(
-        "SELECT id, to_place_id 
-         FROM schedules 
-         WHERE 
-         from_place_id = $1 AND departure_time >= $2;",
-        from_place_sched_id as i32,
-        node_departure_time,
-    )
-    .fetch_all(&db_pool.clone())
-    .await?
-    .into_iter()
-    .map(|n| (n.id as usize, n.to_place_id as usize))
-    .collect();
-
-    let mut edges: Vec<SchedId> = Vec::new();
-    for (sched_id, to_place_id) in sched_destinations.into_iter() {
-        let query = query!(
-            "SELECT id 
-             FROM schedules 
-             WHERE id = $1 
-             AND from_place_id = $2 
-             AND departure_time >= $3;",
-            (sched_id + 1) as i32,
-            (to_place_id) as i32,
-            node_departure_time
-        )
-        .fetch_all(&db_pool)
-        .await?
-        .into_iter()
-        .map(|record| record.id as usize)
-        .collect::<Vec<usize>>();
-
-        edges.extend(query.into_iter());
-    }
+        let mut nodes = Vec::with_capacity(legs.len());
+        for (i, leg) in legs.iter().enumerate() {
+            // legs without one before them only start searches, the other legs
+            // leaving the origin start searches of their own
+            let arrival = i
+                .checked_sub(1)
+                .filter(|&prev| next_legs[prev] == Some(i))
+                .map(|prev| legs[prev].arrival_time.seconds());
+
+            let mut edges = Vec::new();
+            for &sched in &departures[&leg.from_place_id] {
+                let other = &legs[sched];
+                let reachable = sched == i
+                    || other.trip_id != leg.trip_id
+                        && arrival.is_some_and(|arrival| {
+                            let transfer =
+                                transfers.min_transfer(leg.from_place_id, leg.line, other.line);
+                            other.departure_time.seconds() >= arrival + transfer
+                        });
+                let next = match next_legs[sched] {
+                    Some(next) if reachable => next,
+                    _ => continue,
+                };
+
+                let arrival = other.arrival_time.seconds() - departure_time.seconds();
+                let arrival = Weight::try_from(arrival).map_err(|_| {
+                    anyhow!(
+                        "leg {} arrives at {} before {}",
+                        other.id,
+                        other.arrival_time,
+                        departure_time
+                    )
+                })?;
+                edges.push(Edge {
+                    node: next,
+                    leg: sched,
+                    arrival,
+                });
+            }
 
-    Ok(edges)
-}
+            nodes.push(Node {
+                id: leg.id as SchedId,
+                trip_id: leg.trip_id,
+                from_place_id: leg.from_place_id as usize,
+                to_place_id: leg.to_place_id as usize,
+                departure_time: leg.departure_time,
+                arrival_time: leg.arrival_time,
+                edges,
+            });
+        }
 
-async unsafe fn connect_edges(
-    db_pool: PgPool,
-    user_dep_time: &str,
-    nodes: &mut NodeMap,
-) -> Result<()> {
-    for (_, &node) in nodes.iter() {
-        let edges = get_node_edges(db_pool.clone(), &node).await?;
-        (*node).edges =
-            calc_edges_weight(db_pool.clone(), user_dep_time, &node, &edges, nodes).await?;
+        Ok(Self { nodes, index })
     }
 
-    Ok(())
-}
-
-async unsafe fn calc_edges_weight(
-    db_pool: PgPool,
-    user_dep_time: &str,
-    origin_node: &*mut Node,
-    edges: &Vec<SchedId>,
-    nodes: &NodeMap,
-) -> Result<HashMap<NonNull<Node>, Weight>> {
-    let mut node_edges = HashMap::new();
-    for sched_id in edges {
-        let edge = *nodes.get(sched_id).ok_or(anyhow!("Unable to find node"))?;
-        let query = query!(
-            "SELECT arrival_time 
-             FROM schedules 
-             WHERE id = $1",
-            (sched_id - 1) as i32
-        )
-        .fetch_all(&db_pool)
-        .await?
-        .into_iter()
-        .map(|record| {
-            record
-                .arrival_time
-                .map_or(None, |rec| Some(rec.format("%H:%M:%S").to_string()))
-        })
-        .collect::<Vec<Option<String>>>();
-
-        let prev_edge_node_arr_time = {
-            query.first().map_or(None, |arr_time| {
-                arr_time.as_ref().map(|arr_time| arr_time.to_owned())
-            })
-        };
-
-        let cost = {
-            if let Some(arr_time) = prev_edge_node_arr_time {
-                calculate_travel_duration(user_dep_time, &arr_time)?
-            } else {
-                calculate_travel_duration(
-                    user_dep_time,
-                    &(*(*origin_node))
-                        .weight
-                        .expect("sumabog nanaman ulit ang server")
-                        .to_string(),
-                )?
-            }
-        };
-
-        node_edges.insert(NonNull::new_unchecked(edge), cost);
+    pub fn node_index(&self, sched_id: SchedId) -> Option<NodeIndex> {
+        self.index.get(&sched_id).copied()
     }
-
-    Ok(node_edges)
 }
 
-async unsafe fn init_nodes(db_pool: PgPool, departure_time: &str) -> Result<NodeMap> {
-    let mut nodes = HashMap::new();
-    let queries = query!(
-        "SELECT id, from_place_id, to_place_id, departure_time, arrival_time 
-         FROM schedules 
-         WHERE departure_time >= $1;",
-        NaiveTime::from_str(departure_time)?
-    )
-    .fetch_all(&db_pool)
-    .await?;
-
-    for query in queries {
-        let new_node = Box::into_raw(Box::new(Node::new(
-            query.id as usize,
-            query.from_place_id as usize,
-            query.to_place_id as usize,
-            query
-                .departure_time
-                .ok_or(anyhow!("time explodes"))?
-                .format("%H:%M:%S")
-                .to_string(),
-            query
-                .arrival_time
-                .ok_or(anyhow!("time explodes"))?
-                .format("%H:%M:%S")
-                .to_string(),
-        )));
-        nodes.insert((*new_node).id, new_node);
-    }
-
-    Ok(nodes)
-}
+/// Legs of `timetable` a search from `departure_time` on `date` can take, see
+/// `Graph::new`.
+pub fn get_graph_legs(
+    timetable: &Timetable,
+    departure_time: ServiceTime,
+    date: NaiveDate,
+    vehicle: Option<Vehicle>,
+    realtime: &Realtime,
+) -> Vec<GraphLeg> {
+    // a leg is taken from the service day before, of or after `date`, whichever
+    // puts it within a day of `departure_time`. Delayed legs scheduled to leave
+    // earlier are considered too, the first day a leg is predicted in time wins.
+    // Updates only apply to the run of the service day they were given for, and
+    // runs predicted to arrive before they leave are left out.
+    let earliest = departure_time
+        .seconds()
+        .saturating_sub(realtime.max_delay());
+    let latest = departure_time.seconds() + ServiceTime::DAY - 1;
+
+    let mut legs = Vec::new();
+    for leg in &timetable.legs {
+        if leg.status != Some(AvailabilityStatus::AVAILABLE)
+            || vehicle.is_some_and(|vehicle| leg.vehicle_type != Some(vehicle))
+        {
+            continue;
+        }
 
-pub fn parse_time(time: &str) -> Result<usize> {
-    Ok(time
-        .split(':')
-        .take(2)
-        .flat_map(|s| s.chars())
-        .collect::<String>()
-        .parse::<usize>()?)
-}
+        // predicted times of the run of the first day that fits
+        let times = (-1..=1).find_map(|day| {
+            let service_date = date + Duration::days(day.into());
+            let departure = leg.departure_time.seconds() + day * ServiceTime::DAY;
+            let predicted_departure =
+                departure.saturating_add(realtime.departure_delay(leg.id, service_date));
+            let predicted_arrival = (leg.arrival_time.seconds() + day * ServiceTime::DAY)
+                .saturating_add(realtime.arrival_delay(leg.id, service_date));
+            ((earliest..=latest).contains(&departure)
+                && predicted_departure >= departure_time.seconds()
+                && predicted_arrival >= predicted_departure
+                && !realtime.is_cancelled(leg.id, service_date)
+                && timetable.trip_runs_on(leg.trip_id, service_date))
+            .then_some((predicted_departure, predicted_arrival))
+        });
+        if let Some((departure, arrival)) = times {
+            legs.push(GraphLeg {
+                id: leg.id,
+                trip_id: leg.trip_id,
+                line: leg.line,
+                stop_sequence: leg.stop_sequence,
+                from_place_id: leg.from_place_id,
+                to_place_id: leg.to_place_id,
+                departure_time: ServiceTime(departure),
+                arrival_time: ServiceTime(arrival),
+            });
+        }
+    }
 
-fn calculate_travel_duration(departure_time: &str, arrival_time: &str) -> Result<usize> {
-    Ok(parse_time(arrival_time)? - parse_time(departure_time)?)
+    legs
 }
@@ -1,42 +1,158 @@
-use crate::routes::{place::Place, DatabasePool};
+use crate::routes::{
+    auth::{require_session, OptionalSessionToken},
+    fare::{calculate_fare, Fare},
+    place::Place,
+    realtime::RealtimeStore,
+    route::history::{record_route_search, NewRouteSearch},
+    service_time::ServiceTime,
+    transfer::MAX_TRANSFER_SECONDS,
+    DatabasePool, Vehicle,
+};
 use actix_web::{web, HttpResponse, Responder};
 use anyhow::{anyhow, Result};
-use chrono::NaiveTime;
-use graph::{parse_time, Graph, Node};
+use chrono::{Local, NaiveDate, NaiveTime};
+use csa::ConnectionScan;
+use graph::{get_graph_legs, Graph, NodeIndex, Weight};
+use planner::{Journey, JourneyLabel, JourneyPlanner, JourneyQuery, Planner};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Reverse, collections::BinaryHeap, ptr::NonNull};
+use std::{cmp::Reverse, collections::BinaryHeap, str::FromStr};
+use timetable::{Timetable, TimetableCache};
 
+pub mod csa;
 pub mod graph;
+pub mod planner;
+pub mod timetable;
 
 pub async fn shortest_paths(
     slug: web::Path<Slug>,
+    search_param: web::Query<OptionalSessionToken>,
+    filter: web::Query<SearchFilter>,
+    realtime: web::Data<RealtimeStore>,
+    timetable_cache: web::Data<TimetableCache>,
     db_pool: web::Data<DatabasePool>,
 ) -> impl Responder {
-    let mut graph = unsafe {
-        match Graph::new(db_pool.pool.clone(), &slug.departure_time).await {
-            Ok(graph) => graph,
-            Err(_) => return HttpResponse::BadRequest().json("invalid request"),
-        }
+    let user = match &search_param.token {
+        Some(token) => match require_session(token, &db_pool.pool).await {
+            Ok(user) => Some(user),
+            Err(res) => return res,
+        },
+        None => None,
     };
 
-    let paths = unsafe {
-        calculate_shortest_paths(
-            slug.from_place_id,
-            slug.to_place_id,
-            &slug.departure_time,
-            &mut graph,
-        )
+    let time = match ServiceTime::from_str(&slug.departure_time) {
+        Ok(time) => time,
+        Err(_) => return HttpResponse::BadRequest().json("invalid request"),
     };
+    if !(0..=MAX_TRANSFER_SECONDS).contains(&filter.transfer_penalty)
+        || filter.arrive_by && !filter.planner.arrives_by()
+    {
+        return HttpResponse::BadRequest().json("invalid request");
+    }
 
-    match paths {
-        Ok(paths) => match paths {
-            Some(paths) => return HttpResponse::Ok().json(ShortestPaths { paths }),
-            _ => HttpResponse::NotFound().json("haha wala"),
+    let timetable = match timetable_cache.get(&db_pool.pool).await {
+        Ok(timetable) => timetable,
+        Err(_) => return HttpResponse::InternalServerError().json("sumabog ang server"),
+    };
+    if let Some(msg) = missing_place(slug.from_place_id, slug.to_place_id, &timetable) {
+        return HttpResponse::NotFound().json(msg);
+    }
+
+    // arriving by a time, the legs of the day before it are searched
+    let departure_time = if filter.arrive_by {
+        ServiceTime(time.seconds() - ServiceTime::DAY)
+    } else {
+        time
+    };
+    let legs = get_graph_legs(
+        &timetable,
+        departure_time,
+        filter.date.unwrap_or_else(|| Local::now().date_naive()),
+        filter.vehicle,
+        &realtime.snapshot(),
+    );
+    let planner: Box<dyn JourneyPlanner> = match filter.planner {
+        Planner::Csa => Box::new(ConnectionScan::new(legs, &timetable.transfers)),
+        Planner::Dijkstra => match Graph::from_legs(legs, departure_time, &timetable.transfers) {
+            Ok(graph) => Box::new(graph),
+            Err(_) => return HttpResponse::InternalServerError().json("sumabog ang server"),
         },
-        _ => HttpResponse::InternalServerError().json("sumabog ang server"),
+    };
+
+    let journeys = planner.plan(&JourneyQuery {
+        origin_place_id: slug.from_place_id,
+        destination_place_id: slug.to_place_id,
+        time,
+        arrive_by: filter.arrive_by,
+        transfer_penalty: filter.transfer_penalty,
+        max_transfers: filter.max_transfers,
+    });
+
+    if let Some(user) = &user {
+        let search = NewRouteSearch {
+            from_place_id: slug.from_place_id,
+            to_place_id: slug.to_place_id,
+            departure_time: time.time_of_day(),
+            arrive_by: filter.arrive_by,
+            path_count: journeys
+                .as_ref()
+                .map_or(0, |journeys| journeys.len() as i32),
+            best_travel_seconds: journeys
+                .as_ref()
+                .and_then(|journeys| journeys.first())
+                .map(|journey| journey.travel_seconds as i32),
+        };
+        // history is best effort, a failed insert should not fail the search
+        let _ = record_route_search(&user.username, &search, &db_pool.pool).await;
+    }
+
+    let journeys = match journeys {
+        Some(journeys) => journeys,
+        None => return HttpResponse::NotFound().json("haha wala"),
+    };
+
+    match add_fares(journeys, &timetable) {
+        Ok(paths) => HttpResponse::Ok().json(ShortestPaths { paths }),
+        Err(_) => HttpResponse::InternalServerError().json("sumabog ang server"),
+    }
+}
+
+/// Says which end of the search does not exist, if any.
+fn missing_place(
+    origin_place_id: i32,
+    destination_place_id: i32,
+    timetable: &Timetable,
+) -> Option<&'static str> {
+    if !timetable.has_place(origin_place_id) {
+        Some("origin place not found")
+    } else if !timetable.has_place(destination_place_id) {
+        Some("destination place not found")
+    } else {
+        None
     }
 }
 
+/// Prices and labels every journey.
+fn add_fares(journeys: Vec<Journey>, timetable: &Timetable) -> Result<Vec<PricedPath>> {
+    journeys
+        .iter()
+        .map(|journey| {
+            let ridden = journey
+                .schedule_ids
+                .iter()
+                .map(|id| timetable.fare_legs.get(&(*id as i32)).cloned())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| anyhow!("leg of path not found"))?;
+            Ok(PricedPath {
+                schedule_ids: journey.schedule_ids.clone(),
+                travel_seconds: journey.travel_seconds,
+                transfers: journey.transfers,
+                labels: journey.labels(&journeys),
+                fare: calculate_fare(&ridden, &timetable.fare_rules),
+            })
+        })
+        .collect()
+}
+
 #[derive(Deserialize)]
 pub struct Slug {
     from_place_id: i32,
@@ -44,6 +160,25 @@ pub struct Slug {
     departure_time: String,
 }
 
+#[derive(Deserialize)]
+pub struct SearchFilter {
+    /// Restricts the search to a single kind of vehicle, both are used when absent.
+    vehicle: Option<Vehicle>,
+    /// Day of travel, only trips running that day are used. Defaults to today.
+    date: Option<NaiveDate>,
+    #[serde(default)]
+    planner: Planner,
+    /// Seconds a transfer counts as when ranking paths, none by default.
+    #[serde(default)]
+    transfer_penalty: i32,
+    /// Most transfers a path may take, no limit when absent.
+    max_transfers: Option<usize>,
+    /// Reads the time searched as the time to arrive by, finding the latest
+    /// departures that make it.
+    #[serde(default)]
+    arrive_by: bool,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ResponseSchedule {
     id: i32,
@@ -62,63 +197,91 @@ pub struct Res {
     schedules: Vec<ResponseSchedule>,
 }
 
+/// Schedule ids of each path with the seconds it takes from the departure time
+/// to reach the destination.
+pub type Paths = Vec<(Vec<usize>, Weight)>;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PricedPath {
+    /// Legs ridden, in travel order.
+    pub schedule_ids: Vec<usize>,
+    /// Seconds from the departure time to arriving at the destination, waiting
+    /// included. Arriving by a time, seconds from leaving the origin to that
+    /// time.
+    pub travel_seconds: Weight,
+    /// Times the path changes from one trip to another.
+    pub transfers: usize,
+    /// What sets the path apart from the other paths found.
+    pub labels: Vec<JourneyLabel>,
+    pub fare: Fare,
+}
+
+/// Paths no other path beats on both arrival and transfers, the cheapest
+/// first.
 #[derive(Serialize)]
 struct ShortestPaths {
-    paths: Vec<(Vec<usize>, usize)>,
+    paths: Vec<PricedPath>,
 }
 
-// aysuin nalang to ig
-unsafe fn calculate_shortest_paths(
+/// Shortest paths from `origin_place_id` to `destination_place_id`, the
+/// quickest first, or `None` when the destination cannot be reached. Every leg
+/// leaving the destination is reached by the paths no other path to it beats
+/// on both arrival and trips changed.
+pub fn find_shortest_paths(
     origin_place_id: i32,
     destination_place_id: i32,
-    departure_time: &str,
-    graph: &mut Graph,
-) -> Result<Option<Vec<(Vec<usize>, usize)>>> {
-    let starting_points =
-        find_from_place_sched_ids(origin_place_id as usize, departure_time, graph);
-    let dest_points =
-        find_from_place_sched_ids(destination_place_id as usize, departure_time, graph);
-    dijkstra(departure_time, graph)?;
-    Ok(collect_shortest_paths(starting_points, dest_points, graph))
+    departure_time: ServiceTime,
+    graph: &Graph,
+) -> Option<Paths> {
+    let starting_points = find_from_place_nodes(origin_place_id as usize, departure_time, graph);
+    let dest_points: Vec<NodeIndex> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| node.from_place_id == destination_place_id as usize)
+        .map(|(i, _)| i)
+        .collect();
+    let search = dijkstra(&starting_points, graph);
+    collect_shortest_paths(dest_points, &search, graph)
+}
+
+/// A way to reach the place a node leaves from, in seconds since the departure
+/// time, and the label and leg it was reached from.
+struct SearchLabel {
+    node: NodeIndex,
+    weight: Weight,
+    transfers: usize,
+    prev: Option<(usize, NodeIndex)>,
+}
+
+/// Labels found by a search and, for every node, the ones kept.
+struct Search {
+    labels: Vec<SearchLabel>,
+    settled: Vec<Vec<usize>>,
 }
 
-unsafe fn collect_shortest_paths(
-    starting_points: Vec<usize>,
-    dest_points: Vec<usize>,
+fn collect_shortest_paths(
+    dest_points: Vec<NodeIndex>,
+    search: &Search,
     graph: &Graph,
-) -> Option<Vec<(Vec<usize>, usize)>> {
-    let mut sorted_dest_place_ids: Vec<(usize, usize)> = dest_points
+) -> Option<Paths> {
+    let mut shortest_paths: Paths = dest_points
         .into_iter()
-        .filter_map(|dest_id| {
-            let weight = (*graph.nodes[&dest_id]).weight;
-            weight.map(|w| (dest_id, w))
+        .flat_map(|dest| &search.settled[dest])
+        .map(|&label| {
+            // the legs ridden, then the one leaving the destination. Only
+            // starting points have no label before them.
+            let mut shortest_path = vec![graph.nodes[search.labels[label].node].id];
+            let mut prev = search.labels[label].prev;
+            while let Some((label, leg)) = prev {
+                shortest_path.push(graph.nodes[leg].id);
+                prev = search.labels[label].prev;
+            }
+            shortest_path.reverse();
+            (shortest_path, search.labels[label].weight)
         })
         .collect();
-    sorted_dest_place_ids.sort_by_key(|(_, weight)| *weight);
-
-    let shortest_paths: Vec<(Vec<usize>, usize)> =
-        sorted_dest_place_ids
-            .into_iter()
-            .fold(Vec::new(), |mut acc, (id, weight)| {
-                let mut shortest_path = Vec::new();
-                let goal_node = graph.nodes[&id];
-                let mut prev_node = (*goal_node).prev_node;
-                shortest_path.push(id);
-                while let Some(node) = prev_node {
-                    let sched_id = (*node.as_ptr()).id;
-                    shortest_path.push(sched_id);
-                    prev_node = (*node.as_ptr()).prev_node;
-                    if starting_points.contains(&sched_id) {
-                        break;
-                    }
-                }
-                shortest_path.reverse();
-                if !shortest_path.is_empty() {
-                    acc.push((shortest_path, weight));
-                }
-
-                acc
-            });
+    shortest_paths.sort_by_key(|(_, weight)| *weight);
 
     if shortest_paths.is_empty() {
         return None;
@@ -127,64 +290,79 @@ unsafe fn collect_shortest_paths(
     Some(shortest_paths)
 }
 
-unsafe fn find_from_place_sched_ids(
+/// Legs leaving the origin no earlier than `departure_time`.
+fn find_from_place_nodes(
     origin_place_id: usize,
-    departure_time: &str,
-    graph: &mut Graph,
-) -> Vec<usize> {
-    let starting_points = graph
+    departure_time: ServiceTime,
+    graph: &Graph,
+) -> Vec<NodeIndex> {
+    graph
         .nodes
         .iter()
-        .map(|(&sched_id, &node)| -> Result<usize> {
-            let node_from_place_id = (*node).from_place_id;
-            let node_dep_time = parse_time(&(*node).departure_time)?;
-            let departure_time = parse_time(departure_time)?;
-            if node_dep_time >= departure_time && origin_place_id == node_from_place_id {
-                Ok(sched_id)
-            } else {
-                Err(anyhow!("wala kang nakita"))
-            }
-        });
-
-    starting_points.filter_map(|res| res.ok()).collect()
+        .enumerate()
+        .filter(|(_, node)| {
+            node.from_place_id == origin_place_id && node.departure_time >= departure_time
+        })
+        .map(|(i, _)| i)
+        .collect()
 }
 
-unsafe fn dijkstra(departure_time: &str, graph: &mut Graph) -> Result<()> {
-    let nodes = &mut graph.nodes;
-    let min_node_sched_id = match nodes.keys().copied().min() {
-        Some(sched_id) => sched_id,
-        None => return Ok(()),
+/// Searches from every starting point at once, the origin is reached at the
+/// departure time. Labels are settled earliest first, a node keeps a label
+/// only when it changes trips less often than the ones kept before it.
+fn dijkstra(starting_points: &[NodeIndex], graph: &Graph) -> Search {
+    let mut search = Search {
+        labels: Vec::new(),
+        settled: vec![Vec::new(); graph.nodes.len()],
     };
 
-    let first_to_visit_node = nodes[&min_node_sched_id];
-    let first_to_visit_node_dep_time = &(*first_to_visit_node).departure_time;
-    (*first_to_visit_node).weight =
-        Some(parse_time(first_to_visit_node_dep_time)? - parse_time(departure_time)?);
-
-    let mut binary_heap: BinaryHeap<Reverse<*mut Node>> = BinaryHeap::new();
-    binary_heap.push(Reverse(first_to_visit_node));
+    let mut binary_heap = BinaryHeap::new();
+    for &start in starting_points {
+        search.labels.push(SearchLabel {
+            node: start,
+            weight: 0,
+            transfers: 0,
+            prev: None,
+        });
+        binary_heap.push(Reverse((0, 0, search.labels.len() - 1)));
+    }
 
-    while let Some(node) = binary_heap.pop() {
-        let prev_node = node.0;
-        let prev_node_weight = match (*prev_node).weight {
-            Some(w) => w,
-            None => continue,
-        };
+    while let Some(Reverse((prev_weight, prev_transfers, prev_label))) = binary_heap.pop() {
+        let prev_node = search.labels[prev_label].node;
+        if search.settled[prev_node]
+            .iter()
+            .any(|&label| search.labels[label].transfers <= prev_transfers)
+        {
+            // already reached earlier with as few transfers
+            continue;
+        }
+        search.settled[prev_node].push(prev_label);
 
-        let edges = &(*prev_node).edges;
-        for (&edge, &cost) in edges {
-            let travel_weight = prev_node_weight + cost;
-            let edge = edge.as_ptr();
-            let edge_weight = (*edge).weight;
-            if edge_weight.map_or(true, |w| w > travel_weight) {
-                (*edge).weight = Some(travel_weight);
-                (*edge).prev_node = Some(NonNull::new_unchecked(prev_node));
-                if edge_weight.is_none() {
-                    binary_heap.push(Reverse(edge));
-                }
+        // the legs of starting points are boarded, not changed to
+        let boarded = search.labels[prev_label].prev.is_some();
+        for edge in &graph.nodes[prev_node].edges {
+            // edges carry when they arrive rather than how long they take, the
+            // wait for the connection is part of it
+            let travel_weight = edge.arrival.max(prev_weight);
+            let transfers = prev_transfers
+                + usize::from(
+                    boarded && graph.nodes[edge.leg].trip_id != graph.nodes[prev_node].trip_id,
+                );
+            if search.settled[edge.node]
+                .iter()
+                .any(|&label| search.labels[label].transfers <= transfers)
+            {
+                continue;
             }
+            search.labels.push(SearchLabel {
+                node: edge.node,
+                weight: travel_weight,
+                transfers,
+                prev: Some((prev_label, edge.leg)),
+            });
+            binary_heap.push(Reverse((travel_weight, transfers, search.labels.len() - 1)));
         }
     }
 
-    Ok(())
+    search
 }
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    find_shortest_paths,
+    graph::{Graph, Weight},
+};
+use crate::routes::service_time::ServiceTime;
+
+/// What a journey is planned for.
+#[derive(Debug, Clone, Copy)]
+pub struct JourneyQuery {
+    pub origin_place_id: i32,
+    pub destination_place_id: i32,
+    /// Time to leave the origin from, or to reach the destination by when
+    /// `arrive_by` is set.
+    pub time: ServiceTime,
+    /// Plans journeys leaving as late as possible rather than arriving as
+    /// early as possible.
+    pub arrive_by: bool,
+    /// Seconds every transfer adds to the cost journeys are ranked by.
+    pub transfer_penalty: i32,
+    /// Most transfers a journey may take, any number when `None`.
+    pub max_transfers: Option<usize>,
+}
+
+impl JourneyQuery {
+    /// Cost journeys are ranked by, the lowest first.
+    pub fn cost(&self, travel_seconds: usize, transfers: usize) -> i64 {
+        travel_seconds as i64 + transfers as i64 * i64::from(self.transfer_penalty)
+    }
+
+    /// Whether a journey changing trips `transfers` times may be taken.
+    pub fn allows(&self, transfers: usize) -> bool {
+        self.max_transfers.is_none_or(|max| transfers <= max)
+    }
+
+    /// Journeys no other journey beats on both travel time and transfers, and
+    /// within the transfers allowed, the cheapest first. `None` when there
+    /// are none.
+    pub fn pareto_set(&self, mut journeys: Vec<Journey>) -> Option<Vec<Journey>> {
+        journeys.sort_by_key(|journey| (journey.travel_seconds, journey.transfers));
+        let mut fewest = usize::MAX;
+        journeys.retain(|journey| {
+            // a journey arriving no earlier has to change trips less often
+            let kept = journey.transfers < fewest && self.allows(journey.transfers);
+            fewest = fewest.min(journey.transfers);
+            kept
+        });
+        journeys.sort_by_key(|journey| {
+            (
+                self.cost(journey.travel_seconds, journey.transfers),
+                journey.travel_seconds,
+            )
+        });
+
+        (!journeys.is_empty()).then_some(journeys)
+    }
+}
+
+/// A journey found by a planner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Journey {
+    /// Schedule ids of the legs ridden, in travel order.
+    pub schedule_ids: Vec<usize>,
+    /// Seconds from the departure time to arriving at the destination, or
+    /// from leaving the origin to the time to arrive by.
+    pub travel_seconds: Weight,
+    /// Times the journey changes from one trip to another.
+    pub transfers: usize,
+}
+
+impl Journey {
+    /// What sets the journey apart from the other `journeys` found.
+    pub fn labels(&self, journeys: &[Journey]) -> Vec<JourneyLabel> {
+        let mut labels = Vec::new();
+        if journeys
+            .iter()
+            .all(|other| other.travel_seconds >= self.travel_seconds)
+        {
+            labels.push(JourneyLabel::Fastest);
+        }
+        if journeys
+            .iter()
+            .all(|other| other.transfers >= self.transfers)
+        {
+            labels.push(JourneyLabel::FewestTransfers);
+        }
+        labels
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JourneyLabel {
+    /// Arrives first, or leaves last when arriving by a time.
+    Fastest,
+    /// Changes trips the least.
+    FewestTransfers,
+}
+
+/// Finds journeys between two places over legs that are already filtered and
+/// timed for a search, so planners built from the same legs can be compared.
+pub trait JourneyPlanner {
+    /// The pareto set of journeys trading travel time for transfers, see
+    /// `JourneyQuery::pareto_set`. `None` when the destination cannot be
+    /// reached.
+    fn plan(&self, query: &JourneyQuery) -> Option<Vec<Journey>>;
+}
+
+/// Planner a route search runs on.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Planner {
+    /// Connection scan, `csa::ConnectionScan`.
+    #[default]
+    Csa,
+    /// Shortest paths over the graph of legs, `graph::Graph`.
+    Dijkstra,
+}
+
+impl Planner {
+    /// Whether the planner finds journeys arriving by a time.
+    pub fn arrives_by(self) -> bool {
+        self == Self::Csa
+    }
+}
+
+/// The graph only finds paths that end with a leg leaving the destination,
+/// that leg is not ridden and is left out. Its edges are weighed from a
+/// departure time, so it finds nothing arriving by a time.
+impl JourneyPlanner for Graph {
+    fn plan(&self, query: &JourneyQuery) -> Option<Vec<Journey>> {
+        if query.arrive_by {
+            return None;
+        }
+        let paths = find_shortest_paths(
+            query.origin_place_id,
+            query.destination_place_id,
+            query.time,
+            self,
+        )?;
+
+        let transfers = |ids: &[usize]| {
+            let trips: Vec<i32> = ids
+                .iter()
+                .filter_map(|id| self.node_index(*id))
+                .map(|node| self.nodes[node].trip_id)
+                .collect();
+            trips.windows(2).filter(|pair| pair[0] != pair[1]).count()
+        };
+        let journeys = paths
+            .into_iter()
+            .filter_map(|(mut ids, travel_seconds)| {
+                ids.pop();
+                (!ids.is_empty()).then(|| Journey {
+                    transfers: transfers(&ids),
+                    schedule_ids: ids,
+                    travel_seconds,
+                })
+            })
+            .collect();
+
+        query.pareto_set(journeys)
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use sqlx::{postgres::PgListener, Pool, Postgres};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::routes::{
+    begin_snapshot,
+    calendar::{get_calendars_by_id, Calendar},
+    fare::{get_fare_legs, get_fare_rules, FareLeg, FareRule},
+    service_time::ServiceTime,
+    transfer::TransferRules,
+    AvailabilityStatus, Vehicle,
+};
+
+/// Channel the database notifies when anything read into a `Timetable` changes.
+pub const TIMETABLE_CHANNEL: &str = "timetable_changed";
+/// Wait before listening again after the database could not be reached.
+const LISTEN_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// A leg with what searches filter it on.
+#[derive(Debug, Clone)]
+pub struct TimetableLeg {
+    pub id: i32,
+    pub trip_id: i32,
+    pub line: i32,
+    pub stop_sequence: i32,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub vehicle_type: Option<Vehicle>,
+    pub status: Option<AvailabilityStatus>,
+    pub departure_time: ServiceTime,
+    pub arrival_time: ServiceTime,
+}
+
+/// Everything route search reads, loaded from the database at once.
+#[derive(Debug, Default)]
+pub struct Timetable {
+    /// Legs with both times, ordered by id.
+    pub legs: Vec<TimetableLeg>,
+    place_ids: HashSet<i32>,
+    calendars: HashMap<i32, Calendar>,
+    /// Calendar of every trip that has one.
+    trip_calendars: HashMap<i32, i32>,
+    pub fare_rules: Vec<FareRule>,
+    /// What the fare of each leg depends on, by schedule id.
+    pub fare_legs: HashMap<i32, FareLeg>,
+    pub transfers: TransferRules,
+}
+
+impl Timetable {
+    /// Timetable of the given legs without fares or transfer times, for
+    /// searching without a database.
+    pub fn new(
+        mut legs: Vec<TimetableLeg>,
+        calendars: Vec<Calendar>,
+        trip_calendars: HashMap<i32, i32>,
+    ) -> Self {
+        legs.sort_by_key(|leg| leg.id);
+        Self {
+            legs,
+            calendars: calendars
+                .into_iter()
+                .map(|calendar| (calendar.id, calendar))
+                .collect(),
+            trip_calendars,
+            ..Default::default()
+        }
+    }
+
+    /// Reads the timetable from a single snapshot, so every leg comes with
+    /// its fare data. `min_transfer_seconds` applies where no transfer rule of
+    /// the database does.
+    pub async fn load(
+        min_transfer_seconds: i32,
+        db_pool: &Pool<Postgres>,
+    ) -> Result<Self, sqlx::Error> {
+        let mut tx = begin_snapshot(db_pool).await?;
+        let legs = sqlx::query_as!(
+            TimetableLeg,
+            "SELECT id, trip_id, line, stop_sequence, from_place_id, to_place_id,
+             type as \"vehicle_type: Vehicle\", status as \"status: AvailabilityStatus\",
+             departure_time as \"departure_time!: ServiceTime\",
+             arrival_time as \"arrival_time!: ServiceTime\"
+             FROM schedules
+             WHERE departure_time IS NOT NULL AND arrival_time IS NOT NULL
+             ORDER BY id"
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let place_ids = sqlx::query_scalar!("SELECT id FROM places")
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+        let trip_calendars = sqlx::query!(
+            "SELECT id, calendar_id as \"calendar_id!\" FROM trips WHERE calendar_id IS NOT NULL"
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|trip| (trip.id, trip.calendar_id))
+        .collect();
+        let calendars = get_calendars_by_id(None, &mut *tx).await?;
+        let leg_ids: Vec<i32> = legs.iter().map(|leg| leg.id).collect();
+        let fare_rules = get_fare_rules(&mut *tx).await?;
+        let fare_legs = get_fare_legs(&leg_ids, &mut *tx).await?;
+        let transfers = TransferRules::load(min_transfer_seconds, &mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(Self {
+            place_ids,
+            fare_rules,
+            fare_legs,
+            transfers,
+            ..Self::new(legs, calendars, trip_calendars)
+        })
+    }
+
+    /// Whether `place_id` is a place, legs calling there or not.
+    pub fn has_place(&self, place_id: i32) -> bool {
+        self.place_ids.contains(&place_id)
+    }
+
+    /// Whether `trip_id` runs on `day`, trips without a calendar run daily.
+    pub fn trip_runs_on(&self, trip_id: i32, day: NaiveDate) -> bool {
+        match self.trip_calendars.get(&trip_id) {
+            Some(calendar_id) => self
+                .calendars
+                .get(calendar_id)
+                .is_some_and(|calendar| calendar.runs_on(day)),
+            None => true,
+        }
+    }
+}
+
+/// Timetable shared by every search, read again whenever it changes.
+#[derive(Default)]
+pub struct TimetableCache {
+    min_transfer_seconds: i32,
+    state: RwLock<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    /// Bumped on every change, a load started before the last change is stale.
+    version: u64,
+    timetable: Option<Arc<Timetable>>,
+}
+
+impl TimetableCache {
+    /// Cache of timetables changing trips in `min_transfer_seconds` where no
+    /// transfer rule says otherwise.
+    pub fn new(min_transfer_seconds: i32) -> Self {
+        Self {
+            min_transfer_seconds,
+            ..Default::default()
+        }
+    }
+
+    /// The cached timetable, loaded first when there is none yet.
+    pub async fn get(&self, db_pool: &Pool<Postgres>) -> Result<Arc<Timetable>, sqlx::Error> {
+        let version = {
+            let state = self.state.read().unwrap_or_else(|err| err.into_inner());
+            if let Some(timetable) = &state.timetable {
+                return Ok(Arc::clone(timetable));
+            }
+            state.version
+        };
+
+        let timetable = Arc::new(Timetable::load(self.min_transfer_seconds, db_pool).await?);
+        self.store(version, Some(Arc::clone(&timetable)));
+        Ok(timetable)
+    }
+
+    /// Reads the timetable again and swaps it in, searches keep using the old
+    /// one until then. When reading fails the next search tries again.
+    pub async fn reload(&self, db_pool: &Pool<Postgres>) {
+        let version = {
+            let mut state = self.state.write().unwrap_or_else(|err| err.into_inner());
+            state.version += 1;
+            state.version
+        };
+
+        let timetable = Timetable::load(self.min_transfer_seconds, db_pool)
+            .await
+            .ok()
+            .map(Arc::new);
+        self.store(version, timetable);
+    }
+
+    fn store(&self, version: u64, timetable: Option<Arc<Timetable>>) {
+        let mut state = self.state.write().unwrap_or_else(|err| err.into_inner());
+        if state.version == version {
+            state.timetable = timetable;
+        }
+    }
+}
+
+/// Reloads `cache` on every change announced on `TIMETABLE_CHANNEL`, which
+/// covers changes made by the server as well as outside of it such as CLI
+/// imports. Listens again whenever the connection is lost, for as long as the
+/// server runs.
+pub async fn listen_for_changes(cache: &TimetableCache, db_pool: &Pool<Postgres>) {
+    loop {
+        if let Err(err) = reload_on_changes(cache, db_pool).await {
+            eprintln!("lost timetable change notifications: {}", err);
+        }
+        actix_web::rt::time::sleep(LISTEN_RETRY_DELAY).await;
+    }
+}
+
+async fn reload_on_changes(cache: &TimetableCache, db_pool: &Pool<Postgres>) -> Result<()> {
+    let mut listener = PgListener::connect_with(db_pool).await?;
+    listener.listen(TIMETABLE_CHANNEL).await?;
+    loop {
+        // changes made while not listening were missed, so the cache is
+        // reloaded once listening as well. `try_recv` gives `None` when the
+        // connection was lost and made again in between.
+        cache.reload(db_pool).await;
+        listener.try_recv().await?;
+    }
+}
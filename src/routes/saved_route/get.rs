@@ -0,0 +1,33 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::SavedRoute;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn get_saved_routes(
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    let query = sqlx::query_as!(
+        SavedRoute,
+        "SELECT id, name, from_place_id, to_place_id, departure_time, created_at
+         FROM saved_routes WHERE username = $1 ORDER BY created_at DESC, id DESC",
+        user.username
+    )
+    .fetch_all(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(saved_routes) => HttpResponse::Ok().json(saved_routes),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
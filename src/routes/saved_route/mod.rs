@@ -0,0 +1,34 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub mod get;
+pub mod post;
+pub mod slug;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct SavedRoute {
+    pub id: i32,
+    pub name: Option<String>,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub departure_time: Option<NaiveTime>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct SavedRouteRequest {
+    pub name: Option<String>,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    pub departure_time: Option<NaiveTime>,
+}
+
+impl SavedRouteRequest {
+    fn is_valid(&self) -> bool {
+        self.from_place_id != self.to_place_id
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|name| name.chars().count() <= 100)
+    }
+}
@@ -0,0 +1,50 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::{SavedRoute, SavedRouteRequest};
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    DatabasePool, Res,
+};
+
+pub async fn post_saved_route(
+    search_param: web::Query<SessionToken>,
+    request: web::Json<SavedRouteRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "invalid request".to_owned(),
+        });
+    }
+
+    let query = sqlx::query_as!(
+        SavedRoute,
+        "INSERT INTO saved_routes (username, name, from_place_id, to_place_id, departure_time)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, name, from_place_id, to_place_id, departure_time, created_at",
+        user.username,
+        request.name,
+        request.from_place_id,
+        request.to_place_id,
+        request.departure_time
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(saved_route) => HttpResponse::Created().json(saved_route),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(Res {
+                msg: "place not found".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+pub mod delete;
+pub mod put;
+
+#[derive(Deserialize)]
+pub struct SavedRouteSlug {
+    saved_route_id: i32,
+}
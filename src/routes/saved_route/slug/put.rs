@@ -0,0 +1,57 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::SavedRouteSlug;
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    saved_route::{SavedRoute, SavedRouteRequest},
+    DatabasePool, Res,
+};
+
+pub async fn put_saved_route(
+    slug: web::Path<SavedRouteSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<SavedRouteRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    let user = match require_session(&search_param.token, &db_pool.pool).await {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "invalid request".to_owned(),
+        });
+    }
+
+    let query = sqlx::query_as!(
+        SavedRoute,
+        "UPDATE saved_routes
+         SET name = $3, from_place_id = $4, to_place_id = $5, departure_time = $6
+         WHERE id = $1 AND username = $2
+         RETURNING id, name, from_place_id, to_place_id, departure_time, created_at",
+        slug.saved_route_id,
+        user.username,
+        request.name,
+        request.from_place_id,
+        request.to_place_id,
+        request.departure_time
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(saved_route) => HttpResponse::Ok().json(saved_route),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(Res {
+            msg: "saved route not found".to_owned(),
+        }),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(Res {
+                msg: "place not found".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
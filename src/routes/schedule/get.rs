@@ -0,0 +1,54 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use super::Schedule;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    service_time::ServiceTime,
+    AvailabilityStatus, DatabasePool, Res, Vehicle,
+};
+
+#[derive(Deserialize)]
+pub struct ScheduleFilter {
+    line: Option<i32>,
+    place_id: Option<i32>,
+    from_time: Option<ServiceTime>,
+    to_time: Option<ServiceTime>,
+}
+
+pub async fn get_schedules(
+    search_param: web::Query<SessionToken>,
+    filter: web::Query<ScheduleFilter>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query_as!(
+        Schedule,
+        "SELECT id, line, from_place_id, to_place_id, type as \"vehicle_type: Vehicle\",
+         departure_time as \"departure_time: ServiceTime\", arrival_time as \"arrival_time: ServiceTime\",
+         distance, speed, status as \"status: AvailabilityStatus\",
+         trip_id, stop_sequence
+         FROM schedules
+         WHERE ($1::int IS NULL OR line = $1)
+         AND ($2::int IS NULL OR from_place_id = $2 OR to_place_id = $2)
+         AND ($3::int IS NULL OR departure_time >= $3)
+         AND ($4::int IS NULL OR departure_time <= $4)
+         ORDER BY id",
+        filter.line,
+        filter.place_id,
+        filter.from_time.map(ServiceTime::seconds),
+        filter.to_time.map(ServiceTime::seconds)
+    )
+    .fetch_all(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(schedules) => HttpResponse::Ok().json(schedules),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::routes::{service_time::ServiceTime, AvailabilityStatus, Vehicle};
+
+pub mod get;
+pub mod post;
+pub mod slug;
+pub mod status;
+pub mod validation;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Schedule {
+    pub id: i32,
+    pub line: i32,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    #[serde(rename = "type")]
+    pub vehicle_type: Option<Vehicle>,
+    pub departure_time: Option<ServiceTime>,
+    pub arrival_time: Option<ServiceTime>,
+    pub distance: Option<i32>,
+    pub speed: Option<i32>,
+    pub status: Option<AvailabilityStatus>,
+    pub trip_id: i32,
+    pub stop_sequence: i32,
+}
+
+#[derive(Deserialize)]
+pub struct ScheduleRequest {
+    pub line: i32,
+    pub from_place_id: i32,
+    pub to_place_id: i32,
+    #[serde(rename = "type")]
+    pub vehicle_type: Option<Vehicle>,
+    pub departure_time: ServiceTime,
+    pub arrival_time: ServiceTime,
+    pub distance: Option<i32>,
+    pub speed: Option<i32>,
+    pub status: Option<AvailabilityStatus>,
+    /// Vehicle run the leg belongs to, a new run is started when absent.
+    pub trip_id: Option<i32>,
+    /// Position of the leg in its run, the leg is appended when absent.
+    pub stop_sequence: Option<i32>,
+}
+
+impl ScheduleRequest {
+    /// Checks the request against the database, returning what is wrong with it.
+    /// `trip_id` is the run the leg will end up in, if it already exists.
+    pub async fn validate(
+        &self,
+        trip_id: Option<i32>,
+        db_pool: &Pool<Postgres>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        if self.from_place_id == self.to_place_id {
+            return Ok(Some("from_place_id and to_place_id must differ".to_owned()));
+        }
+        if self.arrival_time <= self.departure_time {
+            return Ok(Some("arrival_time must be after departure_time".to_owned()));
+        }
+        if self.distance.is_some_and(|distance| distance < 0)
+            || self.speed.is_some_and(|speed| speed <= 0)
+        {
+            return Ok(Some("distance and speed must be positive".to_owned()));
+        }
+        if self
+            .stop_sequence
+            .is_some_and(|stop_sequence| stop_sequence <= 0)
+        {
+            return Ok(Some("stop_sequence must be positive".to_owned()));
+        }
+
+        let found = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM places WHERE id IN ($1, $2)",
+            self.from_place_id,
+            self.to_place_id
+        )
+        .fetch_one(db_pool)
+        .await?
+        .unwrap_or(0);
+        if found != 2 {
+            return Ok(Some("unknown place".to_owned()));
+        }
+
+        if let Some(trip_id) = trip_id {
+            let line = sqlx::query_scalar!("SELECT line FROM trips WHERE id = $1", trip_id)
+                .fetch_optional(db_pool)
+                .await?;
+            match line {
+                None => return Ok(Some("unknown trip".to_owned())),
+                Some(line) if line != self.line => {
+                    return Ok(Some("trip runs on another line".to_owned()))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Registers `line` if no leg has used it yet.
+pub async fn ensure_line(
+    line: i32,
+    vehicle_type: Option<Vehicle>,
+    conn: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO lines (id, type) VALUES ($1, $2) ON CONFLICT (id) DO NOTHING",
+        line,
+        vehicle_type as Option<Vehicle>
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use actix_web::{web, HttpResponse, Responder};
+use sqlx::{Pool, Postgres};
+
+use super::{ensure_line, Schedule, ScheduleRequest};
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    service_time::ServiceTime,
+    AvailabilityStatus, DatabasePool, Res, Vehicle,
+};
+
+pub async fn post_schedule(
+    search_param: web::Query<SessionToken>,
+    request: web::Json<ScheduleRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    match request.validate(request.trip_id, &db_pool.pool).await {
+        Ok(None) => {}
+        Ok(Some(msg)) => return HttpResponse::BadRequest().json(Res { msg }),
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    }
+
+    match insert_schedule(&request, &db_pool.pool).await {
+        Ok(schedule) => HttpResponse::Created().json(schedule),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => HttpResponse::Conflict()
+            .json(Res {
+                msg: "stop_sequence already taken".to_owned(),
+            }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+async fn insert_schedule(
+    request: &ScheduleRequest,
+    db_pool: &Pool<Postgres>,
+) -> Result<Schedule, sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    ensure_line(request.line, request.vehicle_type, &mut tx).await?;
+    let trip_id = match request.trip_id {
+        Some(trip_id) => trip_id,
+        None => {
+            sqlx::query_scalar!(
+                "INSERT INTO trips (line) VALUES ($1) RETURNING id",
+                request.line
+            )
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+
+    let schedule = sqlx::query_as!(
+        Schedule,
+        "INSERT INTO schedules
+         (line, from_place_id, to_place_id, type, departure_time, arrival_time, distance, speed,
+         status, trip_id, stop_sequence)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, COALESCE($9, 'AVAILABLE'::availability_status),
+         $10, COALESCE($11, (
+           SELECT COALESCE(MAX(stop_sequence), 0) + 1 FROM schedules WHERE trip_id = $10
+         )))
+         RETURNING id, line, from_place_id, to_place_id, type as \"vehicle_type: Vehicle\",
+         departure_time as \"departure_time: ServiceTime\", arrival_time as \"arrival_time: ServiceTime\",
+         distance, speed, status as \"status: AvailabilityStatus\",
+         trip_id, stop_sequence",
+        request.line,
+        request.from_place_id,
+        request.to_place_id,
+        request.vehicle_type as Option<Vehicle>,
+        request.departure_time.seconds(),
+        request.arrival_time.seconds(),
+        request.distance,
+        request.speed,
+        request.status as Option<AvailabilityStatus>,
+        trip_id,
+        request.stop_sequence
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(schedule)
+}
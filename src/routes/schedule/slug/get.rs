@@ -0,0 +1,41 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::ScheduleSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    schedule::Schedule,
+    service_time::ServiceTime,
+    AvailabilityStatus, DatabasePool, Res, Vehicle,
+};
+
+pub async fn find_schedule(
+    slug: web::Path<ScheduleSlug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query_as!(
+        Schedule,
+        "SELECT id, line, from_place_id, to_place_id, type as \"vehicle_type: Vehicle\",
+         departure_time as \"departure_time: ServiceTime\", arrival_time as \"arrival_time: ServiceTime\",
+         distance, speed, status as \"status: AvailabilityStatus\",
+         trip_id, stop_sequence
+         FROM schedules WHERE id = $1",
+        slug.schedule_id
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(schedule) => HttpResponse::Ok().json(schedule),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(Res {
+            msg: "schedule not found".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+pub mod delete;
+pub mod get;
+pub mod put;
+
+#[derive(Deserialize)]
+pub struct ScheduleSlug {
+    schedule_id: i32,
+}
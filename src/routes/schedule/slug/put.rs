@@ -0,0 +1,108 @@
+use actix_web::{web, HttpResponse, Responder};
+use sqlx::{Pool, Postgres};
+
+use super::ScheduleSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    schedule::{ensure_line, Schedule, ScheduleRequest},
+    service_time::ServiceTime,
+    AvailabilityStatus, DatabasePool, Res, Vehicle,
+};
+
+pub async fn put_schedule(
+    slug: web::Path<ScheduleSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<ScheduleRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let current_trip_id = sqlx::query_scalar!(
+        "SELECT trip_id FROM schedules WHERE id = $1",
+        slug.schedule_id
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+    let trip_id = match current_trip_id {
+        Ok(trip_id) => request.trip_id.unwrap_or(trip_id),
+        Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::NotFound().json(Res {
+                msg: "schedule not found".to_owned(),
+            })
+        }
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    };
+
+    match request.validate(Some(trip_id), &db_pool.pool).await {
+        Ok(None) => {}
+        Ok(Some(msg)) => return HttpResponse::BadRequest().json(Res { msg }),
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Res {
+                msg: "server err".to_owned(),
+            })
+        }
+    }
+
+    match update_schedule(slug.schedule_id, trip_id, &request, &db_pool.pool).await {
+        Ok(schedule) => HttpResponse::Ok().json(schedule),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(Res {
+            msg: "schedule not found".to_owned(),
+        }),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => HttpResponse::Conflict()
+            .json(Res {
+                msg: "stop_sequence already taken".to_owned(),
+            }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+async fn update_schedule(
+    schedule_id: i32,
+    trip_id: i32,
+    request: &ScheduleRequest,
+    db_pool: &Pool<Postgres>,
+) -> Result<Schedule, sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    ensure_line(request.line, request.vehicle_type, &mut tx).await?;
+
+    // a leg moved to another run without a position goes after its last leg
+    let schedule = sqlx::query_as!(
+        Schedule,
+        "UPDATE schedules
+         SET line = $2, from_place_id = $3, to_place_id = $4, type = $5, departure_time = $6,
+         arrival_time = $7, distance = $8, speed = $9, status = COALESCE($10, status),
+         trip_id = $11, stop_sequence = COALESCE($12, CASE WHEN trip_id = $11 THEN stop_sequence
+           ELSE (SELECT COALESCE(MAX(stop_sequence), 0) + 1 FROM schedules WHERE trip_id = $11)
+         END)
+         WHERE id = $1
+         RETURNING id, line, from_place_id, to_place_id, type as \"vehicle_type: Vehicle\",
+         departure_time as \"departure_time: ServiceTime\", arrival_time as \"arrival_time: ServiceTime\",
+         distance, speed, status as \"status: AvailabilityStatus\",
+         trip_id, stop_sequence",
+        schedule_id,
+        request.line,
+        request.from_place_id,
+        request.to_place_id,
+        request.vehicle_type as Option<Vehicle>,
+        request.departure_time.seconds(),
+        request.arrival_time.seconds(),
+        request.distance,
+        request.speed,
+        request.status as Option<AvailabilityStatus>,
+        trip_id,
+        request.stop_sequence
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(schedule)
+}
@@ -0,0 +1,79 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use super::Schedule;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    service_time::ServiceTime,
+    AvailabilityStatus, DatabasePool, Res, Vehicle,
+};
+
+/// Selects the legs to update, every given selector has to match.
+#[derive(Deserialize)]
+pub struct StatusRequest {
+    status: AvailabilityStatus,
+    schedule_id: Option<i32>,
+    line: Option<i32>,
+    from_time: Option<ServiceTime>,
+    to_time: Option<ServiceTime>,
+}
+
+pub async fn put_schedule_status(
+    search_param: web::Query<SessionToken>,
+    request: web::Json<StatusRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    if request.schedule_id.is_none()
+        && request.line.is_none()
+        && request.from_time.is_none()
+        && request.to_time.is_none()
+    {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "select a schedule_id, a line or a time window".to_owned(),
+        });
+    }
+    if let (Some(from_time), Some(to_time)) = (request.from_time, request.to_time) {
+        if from_time > to_time {
+            return HttpResponse::BadRequest().json(Res {
+                msg: "from_time must not be after to_time".to_owned(),
+            });
+        }
+    }
+
+    let query = sqlx::query_as!(
+        Schedule,
+        "UPDATE schedules SET status = $1
+         WHERE ($2::int IS NULL OR id = $2)
+         AND ($3::int IS NULL OR line = $3)
+         AND ($4::int IS NULL OR departure_time >= $4)
+         AND ($5::int IS NULL OR departure_time <= $5)
+         RETURNING id, line, from_place_id, to_place_id, type as \"vehicle_type: Vehicle\",
+         departure_time as \"departure_time: ServiceTime\", arrival_time as \"arrival_time: ServiceTime\",
+         distance, speed, status as \"status: AvailabilityStatus\",
+         trip_id, stop_sequence",
+        request.status as AvailabilityStatus,
+        request.schedule_id,
+        request.line,
+        request.from_time.map(ServiceTime::seconds),
+        request.to_time.map(ServiceTime::seconds)
+    )
+    .fetch_all(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(schedules) if schedules.is_empty() => HttpResponse::NotFound().json(Res {
+            msg: "schedule not found".to_owned(),
+        }),
+        Ok(mut schedules) => {
+            schedules.sort_by_key(|schedule| schedule.id);
+            HttpResponse::Ok().json(schedules)
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
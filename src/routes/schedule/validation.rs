@@ -0,0 +1,25 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::{
+    routes::{
+        auth::{require_admin, SessionToken},
+        DatabasePool, Res,
+    },
+    validate::validate_timetable,
+};
+
+pub async fn get_schedule_validation(
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    match validate_timetable(&db_pool.pool).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Result};
+use chrono::{NaiveTime, Timelike};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// Time counted in seconds from the start of a service day. Like GTFS it goes
+/// past 24:00 for trips that run after midnight, and a search can place it a
+/// day before or after the day it is looking at.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[sqlx(transparent)]
+pub struct ServiceTime(pub i32);
+
+impl ServiceTime {
+    pub const DAY: i32 = 24 * 60 * 60;
+
+    pub fn seconds(self) -> i32 {
+        self.0
+    }
+
+    /// Clock time, leaving out which day it falls on.
+    pub fn time_of_day(self) -> NaiveTime {
+        NaiveTime::from_num_seconds_from_midnight_opt(self.0.rem_euclid(Self::DAY) as u32, 0)
+            .unwrap_or_default()
+    }
+}
+
+impl From<NaiveTime> for ServiceTime {
+    fn from(time: NaiveTime) -> Self {
+        Self(time.num_seconds_from_midnight() as i32)
+    }
+}
+
+impl fmt::Display for ServiceTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let seconds = self.0.abs();
+        write!(
+            f,
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+/// Parses `H:MM` or `H:MM:SS`, hours may go past 23.
+impl FromStr for ServiceTime {
+    type Err = anyhow::Error;
+
+    fn from_str(time: &str) -> Result<Self> {
+        let parts: Vec<&str> = time.trim().split(':').collect();
+        let (h, m, s) = match parts.as_slice() {
+            [h, m] if m.len() == 2 => (h, m, &"00"),
+            [h, m, s] if m.len() == 2 && s.len() == 2 => (h, m, s),
+            _ => return Err(anyhow!("invalid time {}", time)),
+        };
+        let (h, m, s): (i32, i32, i32) = (h.parse()?, m.parse()?, s.parse()?);
+        if !(0..48).contains(&h) || !(0..60).contains(&m) || !(0..60).contains(&s) {
+            return Err(anyhow!("invalid time {}", time));
+        }
+
+        Ok(Self(h * 3600 + m * 60 + s))
+    }
+}
+
+impl Serialize for ServiceTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ServiceTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let time = String::deserialize(deserializer)?;
+        time.parse().map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,25 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::routes::{
+    auth::{require_session, SessionToken},
+    route::search::timetable::TimetableCache,
+    DatabasePool, Res,
+};
+
+/// Transfer rules as route search currently uses them.
+pub async fn get_transfers(
+    search_param: web::Query<SessionToken>,
+    timetable_cache: web::Data<TimetableCache>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_session(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    match timetable_cache.get(&db_pool.pool).await {
+        Ok(timetable) => HttpResponse::Ok().json(timetable.transfers.to_transfers()),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
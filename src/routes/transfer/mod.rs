@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Acquire, Postgres};
+use std::collections::HashMap;
+
+pub mod get;
+pub mod slug;
+
+/// Seconds to change between trips when `MIN_TRANSFER_SECONDS` is not set.
+pub const DEFAULT_MIN_TRANSFER_SECONDS: i32 = 2 * 60;
+/// Longest minimum transfer time or transfer penalty accepted, in seconds.
+pub const MAX_TRANSFER_SECONDS: i32 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+pub struct PlaceTransfer {
+    pub place_id: i32,
+    pub min_transfer_seconds: i32,
+}
+
+/// Minimum time to change from a trip of `from_line` to one of `to_line`.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+pub struct LineTransfer {
+    pub from_line: i32,
+    pub to_line: i32,
+    pub min_transfer_seconds: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Transfers {
+    /// Used where no place or line rule applies.
+    pub min_transfer_seconds: i32,
+    pub places: Vec<PlaceTransfer>,
+    pub lines: Vec<LineTransfer>,
+}
+
+#[derive(Deserialize)]
+pub struct TransferRequest {
+    pub min_transfer_seconds: Option<i32>,
+}
+
+impl TransferRequest {
+    pub fn is_valid(&self) -> bool {
+        self.min_transfer_seconds
+            .is_none_or(|seconds| (0..=MAX_TRANSFER_SECONDS).contains(&seconds))
+    }
+}
+
+/// Minimum times to change between trips, the rule of the pair of lines wins
+/// over the rule of the place, which wins over `min_transfer_seconds`.
+#[derive(Debug, Clone, Default)]
+pub struct TransferRules {
+    pub min_transfer_seconds: i32,
+    places: HashMap<i32, i32>,
+    lines: HashMap<(i32, i32), i32>,
+}
+
+impl TransferRules {
+    pub fn new(
+        min_transfer_seconds: i32,
+        places: Vec<PlaceTransfer>,
+        lines: Vec<LineTransfer>,
+    ) -> Self {
+        Self {
+            min_transfer_seconds,
+            places: places
+                .into_iter()
+                .map(|rule| (rule.place_id, rule.min_transfer_seconds))
+                .collect(),
+            lines: lines
+                .into_iter()
+                .map(|rule| ((rule.from_line, rule.to_line), rule.min_transfer_seconds))
+                .collect(),
+        }
+    }
+
+    pub async fn load<'c>(
+        min_transfer_seconds: i32,
+        db: impl Acquire<'c, Database = Postgres>,
+    ) -> Result<Self, sqlx::Error> {
+        let mut conn = db.acquire().await?;
+        let places = sqlx::query_as!(
+            PlaceTransfer,
+            "SELECT id as place_id, min_transfer_seconds as \"min_transfer_seconds!\"
+             FROM places WHERE min_transfer_seconds IS NOT NULL"
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        let lines = sqlx::query_as!(
+            LineTransfer,
+            "SELECT from_line, to_line, min_transfer_seconds FROM line_transfers"
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(Self::new(min_transfer_seconds, places, lines))
+    }
+
+    /// Seconds needed at `place_id` to get off a trip of `from_line` and on a
+    /// trip of `to_line`.
+    pub fn min_transfer(&self, place_id: i32, from_line: i32, to_line: i32) -> i32 {
+        self.lines
+            .get(&(from_line, to_line))
+            .or_else(|| self.places.get(&place_id))
+            .copied()
+            .unwrap_or(self.min_transfer_seconds)
+    }
+
+    /// The rules as listed to clients, ordered by place and by lines.
+    pub fn to_transfers(&self) -> Transfers {
+        let mut places: Vec<PlaceTransfer> = self
+            .places
+            .iter()
+            .map(|(&place_id, &min_transfer_seconds)| PlaceTransfer {
+                place_id,
+                min_transfer_seconds,
+            })
+            .collect();
+        places.sort_by_key(|rule| rule.place_id);
+        let mut lines: Vec<LineTransfer> = self
+            .lines
+            .iter()
+            .map(
+                |(&(from_line, to_line), &min_transfer_seconds)| LineTransfer {
+                    from_line,
+                    to_line,
+                    min_transfer_seconds,
+                },
+            )
+            .collect();
+        lines.sort_by_key(|rule| (rule.from_line, rule.to_line));
+
+        Transfers {
+            min_transfer_seconds: self.min_transfer_seconds,
+            places,
+            lines,
+        }
+    }
+}
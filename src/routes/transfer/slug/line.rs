@@ -0,0 +1,84 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::LineTransferSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    transfer::{LineTransfer, TransferRequest},
+    DatabasePool, Res,
+};
+
+pub async fn put_line_transfer(
+    slug: web::Path<LineTransferSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<TransferRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let min_transfer_seconds = match request.min_transfer_seconds {
+        Some(seconds) if request.is_valid() => seconds,
+        _ => {
+            return HttpResponse::BadRequest().json(Res {
+                msg: "min_transfer_seconds must be between 0 and 3600".to_owned(),
+            })
+        }
+    };
+
+    let query = sqlx::query_as!(
+        LineTransfer,
+        "INSERT INTO line_transfers (from_line, to_line, min_transfer_seconds)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (from_line, to_line)
+         DO UPDATE SET min_transfer_seconds = EXCLUDED.min_transfer_seconds
+         RETURNING from_line, to_line, min_transfer_seconds",
+        slug.from_line,
+        slug.to_line,
+        min_transfer_seconds
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(rule) => HttpResponse::Ok().json(rule),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(Res {
+                msg: "line not found".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
+
+pub async fn delete_line_transfer(
+    slug: web::Path<LineTransferSlug>,
+    search_param: web::Query<SessionToken>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query!(
+        "DELETE FROM line_transfers WHERE from_line = $1 AND to_line = $2",
+        slug.from_line,
+        slug.to_line
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "transfer rule not found".to_owned(),
+        }),
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "transfer rule deleted".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
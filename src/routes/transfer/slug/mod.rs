@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+pub mod line;
+pub mod place;
+
+#[derive(Deserialize)]
+pub struct PlaceTransferSlug {
+    place_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct LineTransferSlug {
+    from_line: i32,
+    to_line: i32,
+}
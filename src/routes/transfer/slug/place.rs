@@ -0,0 +1,47 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::PlaceTransferSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    transfer::TransferRequest,
+    DatabasePool, Res,
+};
+
+/// Sets the minimum transfer time of a place, `null` leaves the place to the
+/// default.
+pub async fn put_place_transfer(
+    slug: web::Path<PlaceTransferSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<TransferRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    if !request.is_valid() {
+        return HttpResponse::BadRequest().json(Res {
+            msg: "min_transfer_seconds must be between 0 and 3600".to_owned(),
+        });
+    }
+
+    let query = sqlx::query!(
+        "UPDATE places SET min_transfer_seconds = $2 WHERE id = $1",
+        slug.place_id,
+        request.min_transfer_seconds
+    )
+    .execute(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(res) if res.rows_affected() == 0 => HttpResponse::NotFound().json(Res {
+            msg: "place not found".to_owned(),
+        }),
+        Ok(_) => HttpResponse::Ok().json(Res {
+            msg: "transfer time updated".to_owned(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+pub mod slug;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Trip {
+    pub id: i32,
+    pub line: i32,
+    pub gtfs_trip_id: Option<String>,
+    /// Runs every day when `None`.
+    pub calendar_id: Option<i32>,
+}
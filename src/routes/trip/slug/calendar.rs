@@ -0,0 +1,50 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use super::TripSlug;
+use crate::routes::{
+    auth::{require_admin, SessionToken},
+    trip::Trip,
+    DatabasePool, Res,
+};
+
+#[derive(Deserialize)]
+pub struct TripCalendarRequest {
+    calendar_id: Option<i32>,
+}
+
+pub async fn put_trip_calendar(
+    slug: web::Path<TripSlug>,
+    search_param: web::Query<SessionToken>,
+    request: web::Json<TripCalendarRequest>,
+    db_pool: web::Data<DatabasePool>,
+) -> impl Responder {
+    if let Err(res) = require_admin(&search_param.token, &db_pool.pool).await {
+        return res;
+    }
+
+    let query = sqlx::query_as!(
+        Trip,
+        "UPDATE trips SET calendar_id = $2 WHERE id = $1
+         RETURNING id, line, gtfs_trip_id, calendar_id",
+        slug.trip_id,
+        request.calendar_id
+    )
+    .fetch_one(&db_pool.pool)
+    .await;
+
+    match query {
+        Ok(trip) => HttpResponse::Ok().json(trip),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(Res {
+            msg: "trip not found".to_owned(),
+        }),
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            HttpResponse::BadRequest().json(Res {
+                msg: "unknown calendar".to_owned(),
+            })
+        }
+        Err(_) => HttpResponse::InternalServerError().json(Res {
+            msg: "server err".to_owned(),
+        }),
+    }
+}
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+pub mod calendar;
+
+#[derive(Deserialize)]
+pub struct TripSlug {
+    trip_id: i32,
+}
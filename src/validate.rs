@@ -0,0 +1,323 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    gtfs::Severity,
+    routes::{service_time::ServiceTime, Vehicle},
+};
+
+/// How far, as a share of the average speed, the recorded speed may be off
+/// before a leg is reported.
+const SPEED_TOLERANCE: f64 = 0.25;
+
+/// Fastest average speed in km/h taken as plausible for a vehicle.
+fn max_speed(vehicle: Option<Vehicle>) -> f64 {
+    match vehicle {
+        Some(Vehicle::TRAIN) => 200.0,
+        Some(Vehicle::BUS) | None => 100.0,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckKind {
+    /// A leg does not leave from where the leg before it in the trip arrives.
+    BrokenChain,
+    /// A leg leaves before the leg before it in the trip arrives.
+    NonMonotonicTime,
+    ArrivalBeforeDeparture,
+    MissingTime,
+    ImplausibleSpeed,
+    /// Recorded speed, distance and travel time do not agree.
+    SpeedMismatch,
+    UnknownPlace,
+    DuplicateLeg,
+    UnreachablePlace,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub kind: CheckKind,
+    pub trip_id: Option<i32>,
+    pub schedule_ids: Vec<i32>,
+    pub place_id: Option<i32>,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ValidationReport {
+    pub trips: usize,
+    pub schedules: usize,
+    pub places: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+
+    fn push(&mut self, severity: Severity, kind: CheckKind, leg: Option<&Leg>, message: String) {
+        self.findings.push(Finding {
+            severity,
+            kind,
+            trip_id: leg.map(|leg| leg.trip_id),
+            schedule_ids: leg.map(|leg| vec![leg.id]).unwrap_or_default(),
+            place_id: None,
+            message,
+        });
+    }
+}
+
+struct Leg {
+    id: i32,
+    trip_id: i32,
+    line: i32,
+    from_place_id: i32,
+    to_place_id: i32,
+    vehicle_type: Option<Vehicle>,
+    departure_time: Option<ServiceTime>,
+    arrival_time: Option<ServiceTime>,
+    distance: Option<i32>,
+    speed: Option<i32>,
+}
+
+/// Checks every trip and place of the timetable and reports what is wrong
+/// with them, errors for data that breaks route search and warnings for data
+/// that is only suspicious.
+pub async fn validate_timetable(db_pool: &Pool<Postgres>) -> Result<ValidationReport> {
+    let legs = sqlx::query_as!(
+        Leg,
+        "SELECT s.id, s.trip_id, s.line, s.from_place_id, s.to_place_id,
+         COALESCE(s.type, lines.type) as \"vehicle_type: Vehicle\",
+         s.departure_time as \"departure_time: ServiceTime\",
+         s.arrival_time as \"arrival_time: ServiceTime\", s.distance, s.speed
+         FROM schedules s
+         JOIN lines ON lines.id = s.line
+         ORDER BY s.trip_id, s.stop_sequence"
+    )
+    .fetch_all(db_pool)
+    .await?;
+    let place_ids: Vec<i32> = sqlx::query_scalar!("SELECT id FROM places ORDER BY id")
+        .fetch_all(db_pool)
+        .await?;
+
+    let mut report = ValidationReport {
+        trips: legs
+            .iter()
+            .map(|leg| leg.trip_id)
+            .collect::<HashSet<_>>()
+            .len(),
+        schedules: legs.len(),
+        places: place_ids.len(),
+        ..Default::default()
+    };
+
+    check_trip_chains(&legs, &mut report);
+    for leg in &legs {
+        check_leg(leg, &mut report);
+    }
+    check_duplicates(&legs, &mut report);
+    check_places(&legs, &place_ids, &mut report);
+
+    report.errors = report
+        .findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    report.warnings = report.findings.len() - report.errors;
+
+    Ok(report)
+}
+
+fn check_trip_chains(legs: &[Leg], report: &mut ValidationReport) {
+    for pair in legs.windows(2) {
+        let (prev, next) = (&pair[0], &pair[1]);
+        if prev.trip_id != next.trip_id {
+            continue;
+        }
+
+        if prev.to_place_id != next.from_place_id {
+            report.push(
+                Severity::Error,
+                CheckKind::BrokenChain,
+                Some(next),
+                format!(
+                    "leaves from place {} but the leg before arrives at place {}",
+                    next.from_place_id, prev.to_place_id
+                ),
+            );
+        }
+        if let (Some(arrival), Some(departure)) = (prev.arrival_time, next.departure_time) {
+            if departure < arrival {
+                report.push(
+                    Severity::Error,
+                    CheckKind::NonMonotonicTime,
+                    Some(next),
+                    format!(
+                        "leaves at {} before the leg before arrives at {}",
+                        departure, arrival
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn check_leg(leg: &Leg, report: &mut ValidationReport) {
+    let (departure, arrival) = match (leg.departure_time, leg.arrival_time) {
+        (Some(departure), Some(arrival)) => (departure, arrival),
+        _ => {
+            report.push(
+                Severity::Error,
+                CheckKind::MissingTime,
+                Some(leg),
+                "has no departure or arrival time".to_owned(),
+            );
+            return;
+        }
+    };
+    if arrival < departure {
+        report.push(
+            Severity::Error,
+            CheckKind::ArrivalBeforeDeparture,
+            Some(leg),
+            format!("arrives at {} before leaving at {}", arrival, departure),
+        );
+        return;
+    }
+
+    let max_speed = max_speed(leg.vehicle_type);
+    if let Some(speed) = leg.speed {
+        if speed <= 0 || f64::from(speed) > max_speed {
+            report.push(
+                Severity::Warning,
+                CheckKind::ImplausibleSpeed,
+                Some(leg),
+                format!("recorded speed of {} km/h", speed),
+            );
+        }
+    }
+
+    let distance = match leg.distance {
+        Some(distance) if distance > 0 => distance,
+        _ => return,
+    };
+    let seconds = arrival.seconds() - departure.seconds();
+    if seconds == 0 {
+        report.push(
+            Severity::Error,
+            CheckKind::ImplausibleSpeed,
+            Some(leg),
+            format!("travels {} m in no time", distance),
+        );
+        return;
+    }
+
+    let average = f64::from(distance) / f64::from(seconds) * 3.6;
+    if average > max_speed {
+        report.push(
+            Severity::Error,
+            CheckKind::ImplausibleSpeed,
+            Some(leg),
+            format!(
+                "travels {} m in {} s, {:.1} km/h on average",
+                distance, seconds, average
+            ),
+        );
+    } else if let Some(speed) = leg.speed.filter(|speed| *speed > 0) {
+        if (f64::from(speed) - average).abs() > average * SPEED_TOLERANCE {
+            report.push(
+                Severity::Warning,
+                CheckKind::SpeedMismatch,
+                Some(leg),
+                format!(
+                    "recorded speed of {} km/h but travels {} m in {} s, {:.1} km/h on average",
+                    speed, distance, seconds, average
+                ),
+            );
+        }
+    }
+}
+
+/// Legs of a line between the same places at the same times.
+fn check_duplicates(legs: &[Leg], report: &mut ValidationReport) {
+    let mut same_legs: HashMap<_, Vec<&Leg>> = HashMap::new();
+    for leg in legs {
+        same_legs
+            .entry((
+                leg.line,
+                leg.from_place_id,
+                leg.to_place_id,
+                leg.departure_time,
+                leg.arrival_time,
+            ))
+            .or_default()
+            .push(leg);
+    }
+
+    let mut duplicates: Vec<Vec<&Leg>> = same_legs
+        .into_values()
+        .filter(|legs| legs.len() > 1)
+        .collect();
+    duplicates.sort_by_key(|legs| legs[0].id);
+    for legs in duplicates {
+        let first = legs[0];
+        report.findings.push(Finding {
+            severity: Severity::Error,
+            kind: CheckKind::DuplicateLeg,
+            trip_id: None,
+            schedule_ids: legs.iter().map(|leg| leg.id).collect(),
+            place_id: None,
+            message: format!(
+                "{} legs of line {} go from place {} to place {} at the same times",
+                legs.len(),
+                first.line,
+                first.from_place_id,
+                first.to_place_id
+            ),
+        });
+    }
+}
+
+fn check_places(legs: &[Leg], place_ids: &[i32], report: &mut ValidationReport) {
+    let known: HashSet<i32> = place_ids.iter().copied().collect();
+    for leg in legs {
+        for place_id in [leg.from_place_id, leg.to_place_id] {
+            if !known.contains(&place_id) {
+                report.findings.push(Finding {
+                    severity: Severity::Error,
+                    kind: CheckKind::UnknownPlace,
+                    trip_id: Some(leg.trip_id),
+                    schedule_ids: vec![leg.id],
+                    place_id: Some(place_id),
+                    message: format!("place {} does not exist", place_id),
+                });
+            }
+        }
+    }
+
+    let left: HashSet<i32> = legs.iter().map(|leg| leg.from_place_id).collect();
+    let reached: HashSet<i32> = legs.iter().map(|leg| leg.to_place_id).collect();
+    for &place_id in place_ids {
+        let message = match (left.contains(&place_id), reached.contains(&place_id)) {
+            (true, true) => continue,
+            (false, false) => "no leg stops at the place",
+            (true, false) => "no leg arrives at the place",
+            (false, true) => "no leg leaves the place",
+        };
+        report.findings.push(Finding {
+            severity: Severity::Warning,
+            kind: CheckKind::UnreachablePlace,
+            trip_id: None,
+            schedule_ids: Vec::new(),
+            place_id: Some(place_id),
+            message: message.to_owned(),
+        });
+    }
+}
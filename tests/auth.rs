@@ -124,3 +124,26 @@ pub async fn get_session_token(db_pool: Pool<Postgres>) -> String {
         .token
         .expect("failed parsing record to string")
 }
+
+// shared with the other test crates, not all of which log in as both roles
+#[allow(dead_code)]
+pub async fn get_admin_session_token(db_pool: Pool<Postgres>) -> String {
+    let token = format!("test-admin-{}", rand::random::<u64>());
+    sqlx::query!("INSERT INTO sessions VALUES ($1, 'admin')", token)
+        .execute(&db_pool)
+        .await
+        .expect("unable to create admin session");
+
+    token
+}
+
+#[allow(dead_code)]
+pub async fn get_user_session_token(db_pool: Pool<Postgres>) -> String {
+    let token = format!("test-user-{}", rand::random::<u64>());
+    sqlx::query!("INSERT INTO sessions VALUES ($1, 'user1')", token)
+        .execute(&db_pool)
+        .await
+        .expect("unable to create user session");
+
+    token
+}
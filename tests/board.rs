@@ -0,0 +1,194 @@
+mod auth;
+
+use actix_web::test;
+use auth::{get_admin_session_token, get_user_session_token};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::{place::slug::board::Board, schedule::Schedule},
+};
+
+#[actix_web::test]
+async fn departure_board_lists_next_departures_with_terminus() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/place/3/departures?token={}&time=08:10:00&limit=5",
+            token
+        ))
+        .to_request();
+    let board: Board = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(board.place_id, 3);
+    assert_eq!(board.entries.len(), 5);
+    assert!(board
+        .entries
+        .windows(2)
+        .all(|pair| pair[0].time <= pair[1].time));
+    assert!(board
+        .entries
+        .iter()
+        .all(|entry| entry.time.to_string().as_str() >= "08:10:00"));
+    // the first line 1 run leaves place 3 at 08:18 and ends back at place 2
+    let first_run = board
+        .entries
+        .iter()
+        .find(|entry| entry.line == 1)
+        .expect("line 1 should leave place 3");
+    assert_eq!(first_run.time.to_string(), "08:18:00");
+    assert_eq!(first_run.terminus.id, 2);
+}
+
+#[actix_web::test]
+async fn arrival_board_lists_next_arrivals_with_origin() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/place/3/arrivals?token={}&time=08:00:00",
+            token
+        ))
+        .to_request();
+    let board: Board = test::call_and_read_body_json(&app, req).await;
+    let first_run = board
+        .entries
+        .iter()
+        .find(|entry| entry.line == 1)
+        .expect("line 1 should reach place 3");
+    assert_eq!(first_run.time.to_string(), "08:16:00");
+    assert_eq!(first_run.terminus.id, 2);
+}
+
+#[actix_web::test]
+async fn arrival_board_after_midnight_lists_runs_of_the_day_before() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/schedule?token={}", token))
+        .set_json(serde_json::json!({
+            "line": 98, "from_place_id": 1, "to_place_id": 2, "type": "BUS",
+            "departure_time": "23:50:00", "arrival_time": "24:10:00"
+        }))
+        .to_request();
+    let schedule: Schedule = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        schedule.arrival_time.map(|time| time.to_string()),
+        Some("24:10:00".to_owned())
+    );
+
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/place/2/arrivals?token={}&time=00:05:00",
+            token
+        ))
+        .to_request();
+    let board: Board = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/schedule/{}?token={}", schedule.id, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let late_run = board
+        .entries
+        .iter()
+        .find(|entry| entry.schedule_id == schedule.id)
+        .expect("the run leaving before midnight should arrive at place 2");
+    assert_eq!(late_run.time.to_string(), "00:10:00");
+}
+
+#[actix_web::test]
+async fn departure_board_before_midnight_lists_runs_of_the_day_after() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/schedule?token={}", token))
+        .set_json(serde_json::json!({
+            "line": 97, "from_place_id": 1, "to_place_id": 2, "type": "BUS",
+            "departure_time": "00:30:00", "arrival_time": "00:50:00"
+        }))
+        .to_request();
+    let schedule: Schedule = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/place/1/departures?token={}&date=2024-06-11&time=23:00:00&limit=50",
+            token
+        ))
+        .to_request();
+    let board: Board = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/schedule/{}?token={}", schedule.id, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let early_run = board
+        .entries
+        .iter()
+        .find(|entry| entry.schedule_id == schedule.id)
+        .expect("the first run of the next day should leave place 1");
+    assert_eq!(early_run.time.to_string(), "24:30:00");
+    assert_eq!(early_run.service_date.to_string(), "2024-06-12");
+}
+
+#[actix_web::test]
+async fn boards_return_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let test_cases = [
+        (
+            "/v1/place/3/departures?token=yep".to_owned(),
+            "invalid token",
+        ),
+        (
+            format!("/v1/place/999999/departures?token={}", token),
+            "unknown place",
+        ),
+        (
+            format!("/v1/place/3/arrivals?token={}&time=25:00", token),
+            "invalid time",
+        ),
+        (
+            format!("/v1/place/3/arrivals?token={}&limit=0", token),
+            "invalid limit",
+        ),
+    ];
+
+    for (uri, msg) in test_cases {
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+mod auth;
+
+use actix_web::test;
+use auth::{get_admin_session_token, get_user_session_token};
+use serde_json::json;
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::{calendar::Calendar, route::search::PricedPath, trip::Trip, CalendarException},
+};
+
+#[actix_web::test]
+async fn calendars_decide_which_days_trips_run() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/calendar?token={}", token))
+        .set_json(json!({
+            "name": "Weekdays",
+            "monday": true, "tuesday": true, "wednesday": true, "thursday": true,
+            "friday": true, "saturday": false, "sunday": false,
+            "start_date": "2024-01-01", "end_date": "2024-12-31"
+        }))
+        .to_request();
+    let calendar: Calendar = test::call_and_read_body_json(&app, req).await;
+
+    // 2024-06-12 is a Wednesday
+    let req = test::TestRequest::put()
+        .uri(&format!(
+            "/v1/calendar/{}/date/2024-06-12?token={}",
+            calendar.id, token
+        ))
+        .set_json(json!({ "exception": "REMOVED" }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/calendar/{}?token={}", calendar.id, token))
+        .to_request();
+    let found: Calendar = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(found.dates.len(), 1);
+    assert_eq!(found.dates[0].exception, CalendarException::REMOVED);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/trip/1/calendar?token={}", token))
+        .set_json(json!({ "calendar_id": calendar.id }))
+        .to_request();
+    let trip: Trip = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(trip.calendar_id, Some(calendar.id));
+
+    // schedule 1 is the first leg of trip 1
+    for (date, runs) in [
+        ("2024-06-11", true),
+        ("2024-06-12", false),
+        ("2024-06-15", false),
+    ] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v1/route/search/2/3/08:00?date={}", date))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        // a day nothing runs on is a 404 without paths
+        let paths: Option<Vec<PricedPath>> =
+            serde_json::from_value(body["paths"].clone()).expect("invalid paths");
+        assert_eq!(
+            paths
+                .unwrap_or_default()
+                .iter()
+                .any(|path| path.schedule_ids.contains(&1)),
+            runs,
+            "trip 1 on {}",
+            date
+        );
+    }
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/calendar/{}?token={}", calendar.id, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 409, "calendar is still in use");
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/trip/1/calendar?token={}", token))
+        .set_json(json!({ "calendar_id": null }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/calendar/{}?token={}", calendar.id, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+}
+
+#[actix_web::test]
+async fn calendars_return_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let week = r#""monday": true, "tuesday": true, "wednesday": true, "thursday": true,
+        "friday": true, "saturday": true, "sunday": true"#;
+    let test_cases = [
+        (
+            "POST",
+            format!("/v1/calendar?token={}", user_token),
+            format!(
+                r#"{{"name": "x", {}, "start_date": "2024-01-01", "end_date": "2024-12-31"}}"#,
+                week
+            ),
+            "user token",
+        ),
+        (
+            "POST",
+            format!("/v1/calendar?token={}", admin_token),
+            format!(
+                r#"{{"name": "x", {}, "start_date": "2024-12-31", "end_date": "2024-01-01"}}"#,
+                week
+            ),
+            "inverted dates",
+        ),
+        (
+            "PUT",
+            format!("/v1/calendar/999999/date/2024-06-12?token={}", admin_token),
+            r#"{"exception": "ADDED"}"#.to_owned(),
+            "unknown calendar",
+        ),
+        (
+            "PUT",
+            format!("/v1/trip/1/calendar?token={}", admin_token),
+            r#"{"calendar_id": 999999}"#.to_owned(),
+            "unknown trip calendar",
+        ),
+        (
+            "PUT",
+            format!("/v1/trip/999999/calendar?token={}", admin_token),
+            r#"{"calendar_id": null}"#.to_owned(),
+            "unknown trip",
+        ),
+    ];
+
+    for (method, uri, payload, msg) in test_cases {
+        let req = match method {
+            "POST" => test::TestRequest::post(),
+            _ => test::TestRequest::put(),
+        }
+        .uri(&uri)
+        .insert_header(actix_web::http::header::ContentType::json())
+        .set_payload(payload)
+        .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/3/08:00?date=2024-13-01")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_client_error(), "invalid search date");
+}
@@ -0,0 +1,186 @@
+mod auth;
+
+use actix_web::{http::header::ContentType, test};
+use auth::{get_admin_session_token, get_user_session_token};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::{
+        fare::{Fare, FareRule},
+        route::search::PricedPath,
+    },
+};
+
+#[actix_web::test]
+async fn quote_charges_rides_zones_and_transfers_by_rule() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool.clone()).await;
+    // 2 -> 3 -> 5 on trip 1, then back to 3 on trip 2
+    let quote = || {
+        test::TestRequest::post()
+            .uri(&format!("/v1/fare/quote?token={}", token))
+            .set_json(serde_json::json!({ "schedule_ids": [1, 2, 15] }))
+            .to_request()
+    };
+    let totals = |fare: &Fare| fare.legs.iter().map(|leg| leg.total).collect::<Vec<_>>();
+
+    let fare: Fare = test::call_and_read_body_json(&app, quote()).await;
+    assert_eq!(fare.currency, "THB");
+    assert_eq!(
+        fare.legs
+            .iter()
+            .map(|leg| (leg.base_fare, leg.transfer_discount))
+            .collect::<Vec<_>>(),
+        [(1600, 0), (0, 0), (1600, 500)]
+    );
+    assert_eq!(totals(&fare), [4585, 946, 2046]);
+    assert_eq!(fare.total, 7577);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/fare/rules?token={}", token))
+        .to_request();
+    let rules: Vec<FareRule> = test::call_and_read_body_json(&app, req).await;
+    let train = rules
+        .iter()
+        .find(|rule| rule.base_fare == 1600)
+        .copied()
+        .expect("train rule should be seeded");
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/fare/rules/TRAIN?token={}", token))
+        .set_json(serde_json::json!({
+            "base_fare": 1000, "per_km": 100, "per_zone": 200, "transfer_discount": 1500
+        }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+    sqlx::query!(
+        "UPDATE places SET fare_zone = CASE id WHEN 3 THEN 'B' ELSE 'A' END WHERE id IN (2, 3, 5)"
+    )
+    .execute(&db_pool)
+    .await
+    .expect("unable to set fare zones");
+
+    let fare: Fare = test::call_and_read_body_json(&app, quote()).await;
+
+    sqlx::query!("UPDATE places SET fare_zone = NULL WHERE id IN (2, 3, 5)")
+        .execute(&db_pool)
+        .await
+        .expect("unable to clear fare zones");
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/fare/rules/TRAIN?token={}", token))
+        .set_json(serde_json::json!({
+            "base_fare": train.base_fare,
+            "per_km": train.per_km,
+            "per_zone": train.per_zone,
+            "transfer_discount": train.transfer_discount
+        }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    // the discount is capped at the base fare
+    assert_eq!(totals(&fare), [3190, 831, 831]);
+    assert_eq!(fare.total, 4852);
+}
+
+#[actix_web::test]
+async fn route_search_prices_every_path() {
+    let server_config = ServerConfig::new().await;
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:00")
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let paths: Vec<PricedPath> =
+        serde_json::from_value(body["paths"].clone()).expect("missing paths");
+    assert!(!paths.is_empty());
+
+    for PricedPath {
+        schedule_ids, fare, ..
+    } in paths
+    {
+        assert_eq!(fare.legs.len(), schedule_ids.len());
+        assert_eq!(
+            fare.total,
+            fare.legs.iter().map(|leg| leg.total).sum::<i32>()
+        );
+        assert!(fare.total > 0);
+    }
+}
+
+#[actix_web::test]
+async fn fare_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let quote_cases = [
+        (r#"{"schedule_ids": []}"#, "no legs"),
+        (r#"{"schedule_ids": [999999]}"#, "unknown schedule"),
+        (r#"{"schedule_ids": [1, 1]}"#, "repeated schedule"),
+        (r#"{"schedule_ids": [1, 3]}"#, "legs not connected"),
+    ];
+
+    for (payload, msg) in quote_cases {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/fare/quote?token={}", user_token))
+            .insert_header(ContentType::json())
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error for {} but got {}",
+            msg,
+            res.status()
+        );
+    }
+
+    let rule_cases = [
+        (
+            user_token.as_str(),
+            "BUS",
+            r#"{"base_fare": 800, "per_km": 50}"#,
+            "user token",
+        ),
+        (
+            admin_token.as_str(),
+            "BUS",
+            r#"{"base_fare": -1, "per_km": 50}"#,
+            "negative amount",
+        ),
+        (
+            admin_token.as_str(),
+            "PLANE",
+            r#"{"base_fare": 800, "per_km": 50}"#,
+            "unknown vehicle",
+        ),
+    ];
+
+    for (token, vehicle, payload, msg) in rule_cases {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v1/fare/rules/{}?token={}", vehicle, token))
+            .insert_header(ContentType::json())
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error for {} but got {}",
+            msg,
+            res.status()
+        );
+    }
+}
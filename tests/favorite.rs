@@ -0,0 +1,165 @@
+mod auth;
+
+use actix_web::{http::header::ContentType, test};
+use auth::get_user_session_token;
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::{favorite::FavoritePlace, saved_route::SavedRoute},
+};
+
+#[actix_web::test]
+async fn favoriting_place_returns_it_in_favorites() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    for _ in 0..2 {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v1/favorite/place/5?token={}", token))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_success(),
+            "favoriting should be idempotent, got {}",
+            res.status()
+        );
+    }
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/favorite/place?token={}", token))
+        .to_request();
+    let favorites: Vec<FavoritePlace> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(favorites.iter().filter(|f| f.place_id == 5).count(), 1);
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/favorite/place/5?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/favorite/place/5?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 404);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/favorite/place/999999?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 404);
+}
+
+#[actix_web::test]
+async fn saved_routes_support_crud() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    // 51 characters but 153 bytes, names are limited in characters
+    let name = "ไปพระบรมมหาราชวัง".repeat(3);
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/saved-route?token={}", token))
+        .insert_header(ContentType::json())
+        .set_payload(format!(
+            r#"{{"name": "{}", "from_place_id": 2, "to_place_id": 1, "departure_time": "08:00"}}"#,
+            name
+        ))
+        .to_request();
+    let saved_route: SavedRoute = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(saved_route.from_place_id, 2);
+    assert_eq!(saved_route.name.as_deref(), Some(name.as_str()));
+
+    let req = test::TestRequest::put()
+        .uri(&format!(
+            "/v1/saved-route/{}?token={}",
+            saved_route.id, token
+        ))
+        .insert_header(ContentType::json())
+        .set_payload(
+            r#"{"name": null, "from_place_id": 3, "to_place_id": 1, "departure_time": null}"#,
+        )
+        .to_request();
+    let updated: SavedRoute = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.id, saved_route.id);
+    assert_eq!(updated.from_place_id, 3);
+    assert!(updated.departure_time.is_none());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/saved-route?token={}", token))
+        .to_request();
+    let saved_routes: Vec<SavedRoute> = test::call_and_read_body_json(&app, req).await;
+    assert!(saved_routes.contains(&updated));
+
+    let req = test::TestRequest::delete()
+        .uri(&format!(
+            "/v1/saved-route/{}?token={}",
+            saved_route.id, token
+        ))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+}
+
+#[actix_web::test]
+async fn saving_route_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let long_name = format!(
+        r#"{{"name": "{}", "from_place_id": 1, "to_place_id": 2}}"#,
+        "a".repeat(101)
+    );
+    let test_cases = [
+        (
+            format!("/v1/saved-route?token={}", token),
+            r#"{"from_place_id": 1, "to_place_id": 1}"#,
+            "same origin and destination",
+        ),
+        (
+            format!("/v1/saved-route?token={}", token),
+            r#"{"from_place_id": 1, "to_place_id": 999999}"#,
+            "unknown place",
+        ),
+        (
+            format!("/v1/saved-route?token={}", token),
+            r#"{"from_place_id": 1, "to_place_id": 2, "departure_time": "25:00"}"#,
+            "invalid departure time",
+        ),
+        (
+            format!("/v1/saved-route?token={}", token),
+            long_name.as_str(),
+            "name too long",
+        ),
+        (
+            "/v1/saved-route?token=yep".to_string(),
+            r#"{"from_place_id": 1, "to_place_id": 2}"#,
+            "invalid token",
+        ),
+    ];
+
+    for (uri, payload, msg) in test_cases {
+        let req = test::TestRequest::post()
+            .uri(&uri)
+            .insert_header(ContentType::json())
+            .set_payload(payload.to_owned())
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
@@ -0,0 +1,336 @@
+mod auth;
+
+use actix_web::test;
+use auth::{get_admin_session_token, get_user_session_token};
+use sqlx::{Pool, Postgres};
+use std::io::{Cursor, Write};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    gtfs::{
+        import::{plan_import, ImportReport},
+        Feed, IssueKind, Severity,
+    },
+    routes::{realtime::LegUpdate, service_time::ServiceTime},
+};
+
+fn build_feed(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        zip.start_file(*name, zip::write::FileOptions::default())
+            .expect("unable to start zip entry");
+        zip.write_all(content.as_bytes())
+            .expect("unable to write zip entry");
+    }
+
+    zip.finish().expect("unable to finish zip").into_inner()
+}
+
+fn sample_feed() -> Vec<u8> {
+    build_feed(&[
+        ("agency.txt", "agency_id,agency_name\nA,Test Agency\n"),
+        (
+            "stops.txt",
+            "stop_id,stop_code,stop_name,stop_lat,stop_lon\n\
+             test-S1,1,Test Stop 1,13.7500,100.4900\n\
+             test-S2,2,Test Stop 2,13.7550,100.4950\n\
+             test-S3,3,Test Stop 3,13.7600,100.5000\n",
+        ),
+        (
+            "routes.txt",
+            "route_id,route_short_name,route_type,route_color\n\
+             test-R1,T1,3,FF0000\n\
+             test-R2,F1,4,00FF00\n",
+        ),
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id\n\
+             test-R1,test-WD,test-T1\n\
+             test-R1,test-WD,test-T2\n\
+             test-R1,test-WD,test-T3\n\
+             test-R2,test-WD,test-T4\n\
+             test-R1,test-WD,test-T5\n\
+             test-R1,test-XX,test-T6\n",
+        ),
+        (
+            "calendar.txt",
+            "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+             test-WD,1,1,1,1,1,0,0,20240101,20241231\n",
+        ),
+        (
+            "calendar_dates.txt",
+            "service_id,date,exception_type\n\
+             test-WD,20240106,1\n\
+             test-HOL,20241225,1\n",
+        ),
+        (
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+             test-T1,08:00:00,08:00:00,test-S1,10\n\
+             test-T1,08:10:00,08:11:00,test-S2,20\n\
+             test-T1,08:20:00,08:20:00,test-S3,30\n\
+             test-T2,09:00:00,09:00:00,test-S1,1\n\
+             test-T2,08:50:00,08:50:00,test-S2,2\n\
+             test-T3,10:00:00,10:00:00,test-S1,1\n\
+             test-T3,10:10:00,10:10:00,test-S9,2\n\
+             test-T5,11:00:00,11:00:00,test-S1,1\n\
+             test-T5,1200000:00:00,1200000:00:00,test-S2,2\n",
+        ),
+    ])
+}
+
+async fn clean_up_feed(db_pool: &Pool<Postgres>) {
+    sqlx::query!("DELETE FROM trips WHERE gtfs_trip_id LIKE 'test-%'")
+        .execute(db_pool)
+        .await
+        .expect("unable to delete imported trips");
+    sqlx::query!("DELETE FROM calendars WHERE gtfs_service_id LIKE 'test-%'")
+        .execute(db_pool)
+        .await
+        .expect("unable to delete imported calendars");
+    sqlx::query!("DELETE FROM lines WHERE gtfs_route_id LIKE 'test-%'")
+        .execute(db_pool)
+        .await
+        .expect("unable to delete imported lines");
+    sqlx::query!("DELETE FROM places WHERE gtfs_stop_id LIKE 'test-%'")
+        .execute(db_pool)
+        .await
+        .expect("unable to delete imported places");
+}
+
+#[actix_web::test]
+async fn importing_gtfs_feed_reports_invalid_trips() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    clean_up_feed(&db_pool).await;
+    let token = get_admin_session_token(db_pool.clone()).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/gtfs/import?token={}&dry_run=true", token))
+        .set_payload(sample_feed())
+        .to_request();
+    let report: ImportReport = test::call_and_read_body_json(&app, req).await;
+
+    assert!(report.dry_run);
+    assert_eq!(report.places_created, 3);
+    assert_eq!(report.calendars_imported, 2);
+    assert_eq!(report.trips_imported, 1);
+    assert_eq!(report.schedules_created, 2);
+    for kind in [
+        IssueKind::InvalidValue,
+        IssueKind::UnknownStop,
+        IssueKind::UnknownService,
+        IssueKind::NonMonotonicTime,
+        IssueKind::UnsupportedField,
+        IssueKind::UnsupportedFile,
+        IssueKind::UnsupportedValue,
+    ] {
+        assert!(
+            report.issues.iter().any(|issue| issue.kind == kind),
+            "expecting a {:?} issue in {:?}",
+            kind,
+            report.issues
+        );
+    }
+
+    let imported =
+        sqlx::query_scalar!("SELECT COUNT(*) FROM places WHERE gtfs_stop_id LIKE 'test-%'")
+            .fetch_one(&db_pool)
+            .await
+            .expect("unable to count places");
+    assert_eq!(imported, Some(0), "dry run should not write anything");
+}
+
+#[actix_web::test]
+async fn importing_gtfs_feed_creates_places_and_schedules() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    clean_up_feed(&db_pool).await;
+    let token = get_admin_session_token(db_pool.clone()).await;
+    for run in 0..2 {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/gtfs/import?token={}", token))
+            .set_payload(sample_feed())
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        // the invalid trips are reported, the valid ones are in
+        assert_eq!(res.status().as_u16(), 200);
+        let report: ImportReport = test::read_body_json(res).await;
+        assert!(report.has_errors());
+        assert_eq!(report.places_created, if run == 0 { 3 } else { 0 });
+        assert_eq!(report.places_updated, if run == 0 { 0 } else { 3 });
+    }
+
+    let legs = sqlx::query!(
+        "SELECT schedules.id, schedules.trip_id, schedules.gtfs_stop_sequence,
+         from_place.gtfs_stop_id as from_stop, to_place.gtfs_stop_id as to_stop,
+         schedules.departure_time as \"departure_time: ServiceTime\", schedules.distance
+         FROM schedules
+         JOIN trips ON trips.id = schedules.trip_id
+         JOIN places from_place ON from_place.id = schedules.from_place_id
+         JOIN places to_place ON to_place.id = schedules.to_place_id
+         WHERE trips.gtfs_trip_id = 'test-T1' ORDER BY schedules.stop_sequence"
+    )
+    .fetch_all(&db_pool)
+    .await
+    .expect("unable to query imported schedules");
+    assert_eq!(legs.len(), 2, "re-importing should replace the trip");
+    assert_eq!(legs[0].from_stop.as_deref(), Some("test-S1"));
+    assert_eq!(legs[1].to_stop.as_deref(), Some("test-S3"));
+    assert_eq!(
+        legs[1].departure_time.map(|time| time.to_string()),
+        Some("08:11:00".to_string())
+    );
+    assert!(legs[0].distance.is_some_and(|distance| distance > 0));
+    assert_eq!(legs[0].gtfs_stop_sequence, Some(10));
+    assert_eq!(legs[1].gtfs_stop_sequence, Some(20));
+
+    // realtime updates find stops by the stop_sequence of the imported feed
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/realtime?token={}", token))
+        .set_json(serde_json::json!({
+            "trips": [{
+                "trip_id": legs[0].trip_id,
+                "stops": [{ "stop_sequence": 20, "arrival_delay": 60 }]
+            }]
+        }))
+        .to_request();
+    let updates: Vec<LegUpdate> = test::call_and_read_body_json(&app, req).await;
+    let update_of = |schedule_id: i32| {
+        updates
+            .iter()
+            .find(|update| update.schedule_id == schedule_id)
+            .expect("both legs should be updated")
+    };
+    assert_eq!(update_of(legs[0].id).arrival_delay, 60);
+    assert_eq!(update_of(legs[1].id).departure_delay, 60);
+
+    // weekdays of 2024, and the Saturday added to them
+    let runs = sqlx::query!(
+        "SELECT calendars.gtfs_service_id,
+         trip_runs_on(trips.id, '2024-01-05') as \"friday!\",
+         trip_runs_on(trips.id, '2024-01-06') as \"added_saturday!\",
+         trip_runs_on(trips.id, '2024-01-07') as \"sunday!\"
+         FROM trips JOIN calendars ON calendars.id = trips.calendar_id
+         WHERE trips.gtfs_trip_id = 'test-T1'"
+    )
+    .fetch_one(&db_pool)
+    .await
+    .expect("trip should have a calendar");
+    assert_eq!(runs.gtfs_service_id.as_deref(), Some("test-WD"));
+    assert!(runs.friday && runs.added_saturday && !runs.sunday);
+
+    clean_up_feed(&db_pool).await;
+}
+
+#[actix_web::test]
+async fn importing_gtfs_feed_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let test_cases = [
+        (user_token.as_str(), sample_feed(), "user token"),
+        (admin_token.as_str(), b"not a zip".to_vec(), "invalid zip"),
+        (
+            admin_token.as_str(),
+            build_feed(&[("stops.txt", "stop_id\nS1\n")]),
+            "missing files",
+        ),
+    ];
+
+    for (token, payload, msg) in test_cases {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/gtfs/import?token={}&dry_run=true", token))
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
+
+#[actix_web::test]
+async fn exporting_gtfs_feed_reconstructs_trips() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/gtfs/export?token={}", token))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    let feed = Feed::read(&body).expect("export should be a GTFS zip");
+
+    for file in [
+        "agency.txt",
+        "stops.txt",
+        "routes.txt",
+        "trips.txt",
+        "stop_times.txt",
+        "calendar.txt",
+    ] {
+        assert!(
+            feed.files.contains_key(file),
+            "expecting {} in export",
+            file
+        );
+    }
+
+    let stop_times = &feed.files["stop_times.txt"];
+    let trip_id = stop_times.column("trip_id").expect("missing trip_id");
+    let first_trip = stop_times
+        .rows
+        .iter()
+        .filter(|row| &row[trip_id] == "T1")
+        .count();
+    assert_eq!(
+        first_trip, 9,
+        "the eight legs of trip 1 should give nine stops"
+    );
+
+    let plan = plan_import(&feed);
+    assert!(
+        plan.issues
+            .iter()
+            .all(|issue| issue.severity != Severity::Error),
+        "export should import cleanly, got {:?}",
+        plan.issues
+    );
+}
+
+#[actix_web::test]
+async fn exporting_gtfs_feed_requires_session() {
+    let server_config = ServerConfig::new().await;
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    for uri in ["/v1/gtfs/export", "/v1/gtfs/export?token=yep"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            uri,
+            res.status()
+        );
+    }
+}
@@ -0,0 +1,303 @@
+mod auth;
+
+use actix_web::{http::header::ContentType, test};
+use auth::{get_admin_session_token, get_user_session_token};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::line::{
+        slug::{generate::GeneratedTimetable, timetable::Timetable},
+        Line,
+    },
+};
+
+#[actix_web::test]
+async fn lines_list_stops_and_service_span() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/line?token={}", token))
+        .to_request();
+    let lines: Vec<Line> = test::call_and_read_body_json(&app, req).await;
+    let line = lines
+        .iter()
+        .find(|line| line.id == 1)
+        .expect("line 1 is missing");
+    let stops: Vec<i32> = line.stops.iter().map(|place| place.id).collect();
+    assert_eq!(stops, [2, 3, 5, 15, 7, 9, 10, 17, 2]);
+    assert_eq!(
+        line.first_departure.map(|time| time.to_string()),
+        Some("08:00:00".to_owned())
+    );
+    assert!(line.last_departure >= line.first_departure);
+    assert!(line.headway.is_some_and(|headway| headway > 0));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/line/1?token={}", token))
+        .to_request();
+    let found: Line = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(found.id, 1);
+    assert_eq!(found.stops.len(), stops.len());
+}
+
+#[actix_web::test]
+async fn line_timetable_has_a_row_per_stop_and_a_column_per_trip() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/line/3/timetable?token={}", token))
+        .to_request();
+    let timetable: Timetable = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(timetable.line, 3);
+    assert!(!timetable.trip_ids.is_empty());
+    assert_eq!(timetable.rows.len(), 4);
+    for row in &timetable.rows {
+        assert_eq!(row.times.len(), timetable.trip_ids.len());
+    }
+    for column in 0..timetable.trip_ids.len() {
+        let times: Vec<_> = timetable
+            .rows
+            .iter()
+            .filter_map(|row| row.times[column])
+            .collect();
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
+
+#[actix_web::test]
+async fn lines_return_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let test_cases = [
+        ("/v1/line?token=yep".to_owned(), "invalid token"),
+        ("/v1/line/1".to_owned(), "missing token"),
+        (format!("/v1/line/999999?token={}", token), "unknown line"),
+        (
+            format!("/v1/line/999999/timetable?token={}", token),
+            "unknown line timetable",
+        ),
+    ];
+
+    for (uri, msg) in test_cases {
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
+
+#[actix_web::test]
+async fn generating_timetable_previews_then_inserts_runs() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    // a calendar that never runs keeps the runs out of searches
+    let calendar_id = sqlx::query_scalar!(
+        "INSERT INTO calendars (name, monday, tuesday, wednesday, thursday, friday, saturday, sunday,
+         start_date, end_date)
+         VALUES ('never', false, false, false, false, false, false, false, '2024-01-01', '2024-01-01')
+         RETURNING id"
+    )
+    .fetch_one(&db_pool)
+    .await
+    .expect("unable to insert calendar");
+    let token = get_admin_session_token(db_pool.clone()).await;
+    let template = serde_json::json!({
+        "type": "BUS",
+        "stops": [2, 3, 5],
+        "run_times": [10, 8],
+        "dwell": 1,
+        "first_departure": "06:00",
+        "last_departure": "07:00",
+        "headway": 20,
+        "distances": [6000, 4000],
+        "calendar_id": calendar_id
+    });
+
+    let req = test::TestRequest::post()
+        .uri(&format!(
+            "/v1/line/9042/generate?token={}&dry_run=true",
+            token
+        ))
+        .set_json(&template)
+        .to_request();
+    let preview: GeneratedTimetable = test::call_and_read_body_json(&app, req).await;
+    assert!(preview.dry_run);
+    assert_eq!(preview.trips.len(), 4);
+    let legs = &preview.trips[1].legs;
+    assert_eq!(
+        legs.iter()
+            .map(|leg| (
+                leg.departure_time.to_string(),
+                leg.arrival_time.to_string(),
+                leg.speed
+            ))
+            .collect::<Vec<_>>(),
+        [
+            ("06:20:00".to_owned(), "06:30:00".to_owned(), Some(36)),
+            ("06:31:00".to_owned(), "06:39:00".to_owned(), Some(30)),
+        ]
+    );
+    assert!(preview.trips.iter().all(|trip| trip.trip_id.is_none()));
+    let line = sqlx::query_scalar!("SELECT id FROM lines WHERE id = 9042")
+        .fetch_optional(&db_pool)
+        .await
+        .expect("unable to query line");
+    assert!(line.is_none(), "a preview should not insert anything");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/line/9042/generate?token={}", token))
+        .set_json(&template)
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 201);
+    let generated: GeneratedTimetable = test::read_body_json(res).await;
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/line/9042/timetable?token={}", token))
+        .to_request();
+    let timetable: Timetable = test::call_and_read_body_json(&app, req).await;
+
+    sqlx::query!("DELETE FROM trips WHERE line = 9042")
+        .execute(&db_pool)
+        .await
+        .expect("unable to delete trips");
+    sqlx::query!("DELETE FROM lines WHERE id = 9042")
+        .execute(&db_pool)
+        .await
+        .expect("unable to delete line");
+    sqlx::query!("DELETE FROM calendars WHERE id = $1", calendar_id)
+        .execute(&db_pool)
+        .await
+        .expect("unable to delete calendar");
+
+    assert!(!generated.dry_run);
+    assert!(generated
+        .trips
+        .iter()
+        .all(|trip| trip.trip_id.is_some() && trip.legs.iter().all(|leg| leg.id.is_some())));
+    assert_eq!(
+        timetable.trip_ids,
+        generated
+            .trips
+            .iter()
+            .filter_map(|trip| trip.trip_id)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        timetable
+            .rows
+            .iter()
+            .map(|row| row.place.id)
+            .collect::<Vec<_>>(),
+        [2, 3, 5]
+    );
+}
+
+#[actix_web::test]
+async fn generating_timetable_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let template = r#""first_departure": "06:00", "last_departure": "07:00""#;
+    let test_cases = [
+        (
+            user_token.as_str(),
+            format!(
+                r#"{{"stops": [2, 3], "run_times": [5], "headway": 10, {}}}"#,
+                template
+            ),
+            "user token",
+        ),
+        (
+            admin_token.as_str(),
+            format!(
+                r#"{{"stops": [2], "run_times": [], "headway": 10, {}}}"#,
+                template
+            ),
+            "single stop",
+        ),
+        (
+            admin_token.as_str(),
+            format!(
+                r#"{{"stops": [2, 3, 5], "run_times": [5], "headway": 10, {}}}"#,
+                template
+            ),
+            "missing run time",
+        ),
+        (
+            admin_token.as_str(),
+            format!(
+                r#"{{"stops": [2, 3], "run_times": [5], "headway": 0, {}}}"#,
+                template
+            ),
+            "no headway",
+        ),
+        (
+            admin_token.as_str(),
+            format!(
+                r#"{{"stops": [2, 999999], "run_times": [5], "headway": 10, {}}}"#,
+                template
+            ),
+            "unknown place",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"stops": [2, 3], "run_times": [5], "headway": 10,
+                "first_departure": "08:00", "last_departure": "07:00"}"#
+                .to_owned(),
+            "first departure after last",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"stops": [2, 3], "run_times": [5], "headway": 1,
+                "first_departure": "00:00", "last_departure": "23:00"}"#
+                .to_owned(),
+            "too many runs",
+        ),
+    ];
+
+    for (token, payload, msg) in test_cases {
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/v1/line/9043/generate?token={}&dry_run=true",
+                token
+            ))
+            .insert_header(ContentType::json())
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
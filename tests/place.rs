@@ -1,7 +1,7 @@
 mod auth;
 
 use actix_web::{http::header::ContentType, test};
-use auth::get_session_token;
+use auth::{get_admin_session_token, get_session_token, get_user_session_token};
 use sqlx::{Pool, Postgres};
 use wsc2017_tp17::{
     config::ServerConfig,
@@ -24,7 +24,10 @@ async fn getting_all_places_returns_valid_response_for_valid_request() {
     let res: Vec<Place> = test::call_and_read_body_json(&app, req).await;
     let places = sqlx::query_as!(
         Place,
-        r#"SELECT id, name, latitude, longitude, x, y, image_path, description FROM places"#
+        r#"SELECT id, name, latitude, longitude, x, y, image_path, description,
+           (SELECT AVG(rating)::float8 FROM reviews WHERE place_id = places.id AND NOT hidden) as average_rating,
+           (SELECT COUNT(*) FROM reviews WHERE place_id = places.id AND NOT hidden) as review_count
+           FROM places"#
     )
     .fetch_all(&db_pool)
     .await
@@ -109,7 +112,7 @@ async fn finding_place_returns_400_for_invalid_request() {
             .await;
 
     let token = get_session_token(db_pool.clone()).await;
-    let test_case = vec![
+    let test_case = [
         ("token=yep".to_string(), "invalid token".to_string()),
         ("token=".to_string(), "empty token".to_string()),
         ("".to_string(), "empty search params".to_string()),
@@ -145,6 +148,94 @@ async fn finding_place_returns_400_for_invalid_request() {
     }
 }
 
+#[actix_web::test]
+async fn finding_place_returns_translation_for_requested_language() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/place/1/translation/th?token={}", admin_token))
+        .insert_header(ContentType::json())
+        .set_payload(r#"{"name": "พระบรมมหาราชวัง", "description": "test"}"#)
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/place/1?token={}&lang=th", admin_token))
+        .to_request();
+    let place: Place = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(place.name.as_deref(), Some("พระบรมมหาราชวัง"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/place/1?token={}", admin_token))
+        .insert_header(("Accept-Language", "fr;q=0.9, th-TH, en;q=0.5"))
+        .to_request();
+    let place: Place = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(place.name.as_deref(), Some("พระบรมมหาราชวัง"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/place/1?token={}&lang=fr", admin_token))
+        .to_request();
+    let place: Place = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        place.name.as_deref(),
+        Some("The Grand Palace"),
+        "missing translations should fall back to the default language"
+    );
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/place/1/translation/th?token={}", admin_token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+}
+
+#[actix_web::test]
+async fn managing_translations_requires_admin() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let user_token = get_user_session_token(db_pool.clone()).await;
+    let admin_token = get_admin_session_token(db_pool).await;
+    let test_cases = [
+        (
+            format!("/v1/place/1/translation/th?token={}", user_token),
+            "user token",
+        ),
+        (
+            "/v1/place/1/translation/th?token=yep".to_string(),
+            "invalid token",
+        ),
+        (
+            format!("/v1/place/1/translation/en?token={}", admin_token),
+            "default language",
+        ),
+    ];
+
+    for (uri, msg) in test_cases {
+        let req = test::TestRequest::put()
+            .uri(&uri)
+            .insert_header(ContentType::json())
+            .set_payload(r#"{"name": "x", "description": null}"#)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
+
 // TODO:
 // CREATE Test
 // UPDATE Test
@@ -0,0 +1,392 @@
+mod auth;
+
+use actix_web::{http::header::ContentType, test};
+use auth::{get_admin_session_token, get_user_session_token};
+use chrono::{Duration, Local, NaiveDate};
+use prost::Message;
+use wsc2017_tp17::{
+    config::ServerConfig,
+    gtfs::realtime::{
+        FeedEntity, FeedMessage, FeedTripUpdate, StopTimeEvent, StopTimeUpdate, TripDescriptor,
+        TripScheduleRelationship,
+    },
+    routes::{
+        gtfs::realtime::RealtimeReport, place::slug::board::Board, realtime::LegUpdate,
+        route::search::PricedPath, schedule::Schedule,
+    },
+};
+
+#[actix_web::test]
+async fn delays_shift_departure_board_until_cleared() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    let board_uri = format!(
+        "/v1/place/3/departures?token={}&time=08:10:00&limit=50",
+        token
+    );
+    let req = test::TestRequest::get().uri(&board_uri).to_request();
+    let board: Board = test::call_and_read_body_json(&app, req).await;
+    let first = board
+        .entries
+        .first()
+        .expect("place 3 should have departures");
+    assert_eq!(first.delay, 0);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/realtime?token={}", token))
+        .set_json(serde_json::json!({
+            "trips": [{ "trip_id": first.trip_id, "delay": 300 }]
+        }))
+        .to_request();
+    let updates: Vec<LegUpdate> = test::call_and_read_body_json(&app, req).await;
+    assert!(updates
+        .iter()
+        .any(|update| update.schedule_id == first.schedule_id && update.departure_delay == 300));
+
+    let req = test::TestRequest::get().uri(&board_uri).to_request();
+    let board: Board = test::call_and_read_body_json(&app, req).await;
+    let delayed = board
+        .entries
+        .iter()
+        .find(|entry| entry.schedule_id == first.schedule_id)
+        .expect("a delayed run should stay on the board");
+    assert_eq!(delayed.delay, 300);
+    assert_eq!(
+        delayed.expected_time.seconds(),
+        delayed.time.seconds() + 300
+    );
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/realtime?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/realtime?token={}", token))
+        .to_request();
+    let updates: Vec<LegUpdate> = test::call_and_read_body_json(&app, req).await;
+    assert!(updates.is_empty());
+}
+
+#[actix_web::test]
+async fn updates_only_apply_to_the_run_of_their_service_day() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    let board_uri = |date: NaiveDate| {
+        format!(
+            "/v1/place/3/departures?token={}&date={}&time=08:10:00&limit=50",
+            token, date
+        )
+    };
+    let today = Local::now().date_naive();
+    let tomorrow = today + Duration::days(1);
+    let req = test::TestRequest::get()
+        .uri(&board_uri(tomorrow))
+        .to_request();
+    let board: Board = test::call_and_read_body_json(&app, req).await;
+    let first = board
+        .entries
+        .first()
+        .expect("place 3 should have departures");
+    assert_eq!(first.service_date, tomorrow);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/realtime?token={}", token))
+        .set_json(serde_json::json!({
+            "schedules": [{
+                "schedule_id": first.schedule_id,
+                "service_date": tomorrow,
+                "delay": 300
+            }]
+        }))
+        .to_request();
+    let updates: Vec<LegUpdate> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].service_date, tomorrow);
+
+    for (date, delay) in [(today, 0), (tomorrow, 300)] {
+        let req = test::TestRequest::get().uri(&board_uri(date)).to_request();
+        let board: Board = test::call_and_read_body_json(&app, req).await;
+        let entry = board
+            .entries
+            .iter()
+            .find(|entry| entry.schedule_id == first.schedule_id && entry.service_date == date)
+            .expect("the run should be on the board");
+        assert_eq!(entry.delay, delay, "date: {}", date);
+    }
+}
+
+#[actix_web::test]
+async fn cancelled_legs_are_skipped_by_search_and_boards() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/schedule/2?token={}", token))
+        .to_request();
+    let schedule: Schedule = test::call_and_read_body_json(&app, req).await;
+    let departure = schedule
+        .departure_time
+        .expect("leg 2 should have a departure");
+    let board_uri = format!(
+        "/v1/place/{}/departures?token={}&time={}&limit=50",
+        schedule.from_place_id, token, departure
+    );
+
+    for (cancelled, expect_leg) in [(false, true), (true, false)] {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/realtime?token={}", token))
+            .set_json(serde_json::json!({
+                "schedules": [{ "schedule_id": 2, "cancelled": cancelled }]
+            }))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success(), "status: {}", res.status());
+
+        let req = test::TestRequest::get()
+            .uri("/v1/route/search/2/5/08:00")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let paths: Vec<PricedPath> =
+            serde_json::from_value(body["paths"].clone()).expect("missing paths");
+        assert_eq!(
+            paths.iter().any(|path| path.schedule_ids.contains(&2)),
+            expect_leg,
+            "cancelled: {}",
+            cancelled
+        );
+
+        let req = test::TestRequest::get().uri(&board_uri).to_request();
+        let board: Board = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            board.entries.iter().any(|entry| entry.schedule_id == 2),
+            expect_leg,
+            "cancelled: {}",
+            cancelled
+        );
+    }
+}
+
+#[actix_web::test]
+async fn gtfs_realtime_feed_updates_known_trips() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let trips = sqlx::query!(
+        "SELECT trip_id, array_agg(id ORDER BY stop_sequence) as \"legs!\"
+         FROM schedules GROUP BY trip_id HAVING COUNT(*) >= 2 ORDER BY trip_id LIMIT 2"
+    )
+    .fetch_all(&db_pool)
+    .await
+    .expect("unable to query trips");
+    let (delayed, cancelled) = (&trips[0], &trips[1]);
+
+    let trip_update = |trip_id: String, stop_time_update, schedule_relationship| FeedEntity {
+        id: Some(trip_id.clone()),
+        is_deleted: None,
+        trip_update: Some(FeedTripUpdate {
+            trip: Some(TripDescriptor {
+                trip_id: Some(trip_id),
+                start_date: None,
+                schedule_relationship,
+            }),
+            stop_time_update,
+            delay: None,
+        }),
+    };
+    let feed = FeedMessage {
+        header: None,
+        entity: vec![
+            // late from the second stop on, so the first leg only arrives late
+            trip_update(
+                format!("T{}", delayed.trip_id),
+                vec![StopTimeUpdate {
+                    stop_sequence: Some(2),
+                    arrival: Some(StopTimeEvent {
+                        delay: Some(120),
+                        time: None,
+                    }),
+                    departure: None,
+                    stop_id: None,
+                    schedule_relationship: None,
+                }],
+                None,
+            ),
+            trip_update(
+                format!("T{}", cancelled.trip_id),
+                Vec::new(),
+                Some(TripScheduleRelationship::Canceled as i32),
+            ),
+            trip_update("nope".to_owned(), Vec::new(), None),
+            // neither of the next two replaces the first update of the trip,
+            // too far off to be a delay
+            trip_update(
+                format!("T{}", delayed.trip_id),
+                vec![StopTimeUpdate {
+                    stop_sequence: Some(2),
+                    arrival: Some(StopTimeEvent {
+                        delay: None,
+                        time: Some(i64::MIN),
+                    }),
+                    departure: None,
+                    stop_id: None,
+                    schedule_relationship: None,
+                }],
+                None,
+            ),
+            // past the last stop, not taken for the last stop
+            trip_update(
+                format!("T{}", delayed.trip_id),
+                vec![StopTimeUpdate {
+                    stop_sequence: Some(99),
+                    arrival: Some(StopTimeEvent {
+                        delay: Some(60),
+                        time: None,
+                    }),
+                    departure: None,
+                    stop_id: None,
+                    schedule_relationship: None,
+                }],
+                None,
+            ),
+        ],
+    };
+
+    let token = get_admin_session_token(db_pool).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/gtfs/realtime?token={}&ttl=60", token))
+        .set_payload(feed.encode_to_vec())
+        .to_request();
+    let report: RealtimeReport = test::call_and_read_body_json(&app, req).await;
+
+    let update_of = |schedule_id: i32| {
+        report
+            .updates
+            .iter()
+            .find(|update| update.schedule_id == schedule_id)
+            .copied()
+    };
+    let first_leg = update_of(delayed.legs[0]).expect("first leg should be updated");
+    assert_eq!(
+        (first_leg.departure_delay, first_leg.arrival_delay),
+        (0, 120)
+    );
+    let second_leg = update_of(delayed.legs[1]).expect("second leg should be updated");
+    assert_eq!(
+        (second_leg.departure_delay, second_leg.arrival_delay),
+        (120, 120)
+    );
+    assert!(cancelled
+        .legs
+        .iter()
+        .all(|&leg| update_of(leg).is_some_and(|update| update.cancelled)));
+    let delayed_entity = format!("T{}", delayed.trip_id);
+    let skipped: Vec<(&str, &str)> = report
+        .skipped
+        .iter()
+        .map(|entity| (entity.entity_id.as_str(), entity.reason.as_str()))
+        .collect();
+    assert_eq!(
+        skipped,
+        [
+            ("nope", "unknown trip"),
+            (delayed_entity.as_str(), "time out of range"),
+            (delayed_entity.as_str(), "unknown stop_sequence")
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn realtime_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let test_cases = [
+        (
+            user_token.as_str(),
+            r#"{"schedules": [{"schedule_id": 1, "delay": 60}]}"#,
+            "user token",
+        ),
+        (admin_token.as_str(), r#"{}"#, "no updates"),
+        (
+            admin_token.as_str(),
+            r#"{"ttl": 0, "schedules": [{"schedule_id": 1, "delay": 60}]}"#,
+            "invalid ttl",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"schedules": [{"schedule_id": 999999, "delay": 60}]}"#,
+            "unknown schedule",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"trips": [{"trip_id": 999999, "delay": 60}]}"#,
+            "unknown trip",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"trips": [{"trip_id": 1, "stops": [{"stop_sequence": 99, "arrival_delay": 60}]}]}"#,
+            "stop out of range",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"schedules": [{"schedule_id": 1, "delay": 2147483647}]}"#,
+            "schedule delay out of range",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"trips": [{"trip_id": 1, "delay": -86401}]}"#,
+            "trip delay out of range",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"trips": [{"trip_id": 1, "stops": [{"stop_sequence": 1, "departure_delay": 2147483647}]}]}"#,
+            "stop delay out of range",
+        ),
+    ];
+
+    for (token, payload, msg) in test_cases {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/realtime?token={}", token))
+            .insert_header(ContentType::json())
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error for {} but got {}",
+            msg,
+            res.status()
+        );
+    }
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/gtfs/realtime?token={}", admin_token))
+        .set_payload("not a protobuf")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 400);
+}
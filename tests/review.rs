@@ -0,0 +1,216 @@
+mod auth;
+
+use actix_web::{http::header::ContentType, test};
+use auth::{get_admin_session_token, get_user_session_token};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::{
+        place::Place,
+        review::{Review, Reviews},
+    },
+};
+
+const PLACE_ID: i32 = 2;
+
+#[actix_web::test]
+async fn reviewing_place_updates_rating_summary() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    sqlx::query!(
+        "DELETE FROM reviews WHERE place_id = $1 AND username = 'user1'",
+        PLACE_ID
+    )
+    .execute(&db_pool)
+    .await
+    .expect("unable to clean up reviews");
+    let token = get_user_session_token(db_pool.clone()).await;
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/place/{}/review?token={}", PLACE_ID, token))
+        .insert_header(ContentType::json())
+        .set_payload(r#"{"rating": 4, "text": "nice view"}"#)
+        .to_request();
+    let review: Review = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(review.rating, 4);
+    assert_eq!(review.username, "user1");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/place/{}/review?token={}", PLACE_ID, token))
+        .insert_header(ContentType::json())
+        .set_payload(r#"{"rating": 2, "text": null}"#)
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 409, "second review should conflict");
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/review/{}?token={}", review.id, token))
+        .insert_header(ContentType::json())
+        .set_payload(r#"{"rating": 5, "text": "even better"}"#)
+        .to_request();
+    let review: Review = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(review.rating, 5);
+    assert!(review.updated_at.is_some());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/place/{}?token={}", PLACE_ID, token))
+        .to_request();
+    let place: Place = test::call_and_read_body_json(&app, req).await;
+    let expected = sqlx::query!(
+        "SELECT AVG(rating)::float8 as average, COUNT(*) as count
+         FROM reviews WHERE place_id = $1 AND NOT hidden",
+        PLACE_ID
+    )
+    .fetch_one(&db_pool)
+    .await
+    .expect("unable to compute rating summary");
+    assert_eq!(place.average_rating, expected.average);
+    assert_eq!(place.review_count, expected.count);
+
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/place/{}/review?token={}&page=1&per_page=100",
+            PLACE_ID, token
+        ))
+        .to_request();
+    let reviews: Reviews = test::call_and_read_body_json(&app, req).await;
+    assert!(reviews.reviews.iter().any(|r| r.id == review.id));
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/review/{}?token={}", review.id, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+}
+
+#[actix_web::test]
+async fn hidden_reviews_are_only_listed_for_admins() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let review_id = sqlx::query_scalar!(
+        "INSERT INTO reviews (place_id, username, rating, text) VALUES (3, 'user2', 1, 'spam')
+         ON CONFLICT (place_id, username) DO UPDATE SET hidden = FALSE
+         RETURNING id",
+    )
+    .fetch_one(&db_pool)
+    .await
+    .expect("unable to insert review");
+    let user_token = get_user_session_token(db_pool.clone()).await;
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+
+    let req = test::TestRequest::put()
+        .uri(&format!(
+            "/v1/review/{}/visibility?token={}",
+            review_id, user_token
+        ))
+        .insert_header(ContentType::json())
+        .set_payload(r#"{"hidden": true}"#)
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 403, "users cannot moderate reviews");
+
+    let req = test::TestRequest::put()
+        .uri(&format!(
+            "/v1/review/{}/visibility?token={}",
+            review_id, admin_token
+        ))
+        .insert_header(ContentType::json())
+        .set_payload(r#"{"hidden": true}"#)
+        .to_request();
+    let review: Review = test::call_and_read_body_json(&app, req).await;
+    assert!(review.hidden);
+
+    let list_uri = |token: &str| format!("/v1/place/3/review?token={}&per_page=100", token);
+    let req = test::TestRequest::get()
+        .uri(&list_uri(&user_token))
+        .to_request();
+    let reviews: Reviews = test::call_and_read_body_json(&app, req).await;
+    assert!(reviews.reviews.iter().all(|r| r.id != review_id));
+
+    let req = test::TestRequest::get()
+        .uri(&list_uri(&admin_token))
+        .to_request();
+    let reviews: Reviews = test::call_and_read_body_json(&app, req).await;
+    assert!(reviews.reviews.iter().any(|r| r.id == review_id));
+}
+
+#[actix_web::test]
+async fn listing_reviews_far_past_the_last_page_is_empty() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/place/{}/review?token={}&page={}&per_page=100",
+            PLACE_ID,
+            token,
+            i64::MAX
+        ))
+        .to_request();
+    let reviews: Reviews = test::call_and_read_body_json(&app, req).await;
+    assert!(reviews.reviews.is_empty());
+    assert_eq!(reviews.page, i64::MAX);
+}
+
+#[actix_web::test]
+async fn reviewing_place_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let test_cases = [
+        (
+            format!("/v1/place/{}/review?token={}", PLACE_ID, token),
+            r#"{"rating": 6}"#,
+            "rating out of range",
+        ),
+        (
+            format!("/v1/place/{}/review?token=yep", PLACE_ID),
+            r#"{"rating": 3}"#,
+            "invalid token",
+        ),
+        (
+            format!("/v1/place/999999/review?token={}", token),
+            r#"{"rating": 3}"#,
+            "unknown place",
+        ),
+        (
+            format!("/v1/review/999999?token={}", token),
+            r#"{"rating": 3}"#,
+            "unknown review",
+        ),
+    ];
+
+    for (uri, payload, msg) in test_cases {
+        let req = if uri.starts_with("/v1/review") {
+            test::TestRequest::put()
+        } else {
+            test::TestRequest::post()
+        }
+        .uri(&uri)
+        .insert_header(ContentType::json())
+        .set_payload(payload)
+        .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
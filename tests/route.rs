@@ -0,0 +1,275 @@
+mod auth;
+
+use actix_web::test;
+use auth::{get_admin_session_token, get_user_session_token};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::{
+        route::{
+            history::{popular::PopularRoute, RouteSearches},
+            search::PricedPath,
+        },
+        schedule::Schedule,
+    },
+};
+
+#[actix_web::test]
+async fn searching_route_with_token_records_history() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/route/search/2/5/08:00?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/route/history?token={}", token))
+        .to_request();
+    let history: RouteSearches = test::call_and_read_body_json(&app, req).await;
+    let search = history.searches.first().expect("search was not recorded");
+    assert_eq!((search.from_place_id, search.to_place_id), (2, 5));
+    assert!(!search.arrive_by);
+    assert!(search.path_count > 0);
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/route/history?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/route/history?token={}", token))
+        .to_request();
+    let history: RouteSearches = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(history.total, 0);
+}
+
+#[actix_web::test]
+async fn searching_route_with_invalid_token_returns_401() {
+    let server_config = ServerConfig::new().await;
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:00?token=yep")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn searching_route_between_unknown_places_returns_404() {
+    let server_config = ServerConfig::new().await;
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let cases = [
+        ("/v1/route/search/999999/5/08:00", "unknown origin"),
+        ("/v1/route/search/2/999999/08:00", "unknown destination"),
+    ];
+
+    for (uri, msg) in cases {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status().as_u16(), 404, "expecting 404 for {}", msg);
+    }
+}
+
+#[actix_web::test]
+async fn popular_routes_are_only_available_to_admins() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let user_token = get_user_session_token(db_pool.clone()).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/route/search/2/1/08:00?token={}", user_token))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/route/history/popular?token={}", user_token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 403);
+
+    let admin_token = get_admin_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/route/history/popular?token={}&limit=100",
+            admin_token
+        ))
+        .to_request();
+    let popular: Vec<PopularRoute> = test::call_and_read_body_json(&app, req).await;
+    assert!(popular
+        .iter()
+        .any(|route| (route.from_place_id, route.to_place_id) == (2, 1)));
+}
+
+fn path_ids(body: serde_json::Value) -> Vec<usize> {
+    let paths: Vec<PricedPath> =
+        serde_json::from_value(body["paths"].clone()).expect("missing paths");
+    paths
+        .into_iter()
+        .flat_map(|path| path.schedule_ids)
+        .collect()
+}
+
+#[actix_web::test]
+async fn searching_route_honors_vehicle_and_status() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let timetable_cache = server_config.timetable_cache.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:00?vehicle=TRAIN")
+        .to_request();
+    let ids = path_ids(test::call_and_read_body_json(&app, req).await);
+    let trains = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM schedules WHERE id = ANY($1) AND type = 'TRAIN'",
+        &ids.iter().map(|&id| id as i32).collect::<Vec<_>>()
+    )
+    .fetch_one(&db_pool)
+    .await
+    .expect("unable to count schedules");
+    let mut unique_ids = ids.clone();
+    unique_ids.sort();
+    unique_ids.dedup();
+    assert_eq!(trains, Some(unique_ids.len() as i64));
+
+    // a leg of its own, the seed legs are searched by other tests meanwhile
+    let token = get_admin_session_token(db_pool.clone()).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/schedule?token={}", token))
+        .set_json(serde_json::json!({
+            "line": 96, "from_place_id": 1, "to_place_id": 4, "type": "BUS",
+            "departure_time": "03:00:00", "arrival_time": "03:10:00"
+        }))
+        .to_request();
+    let schedule: Schedule = test::call_and_read_body_json(&app, req).await;
+    let leg_id = schedule.id as usize;
+
+    let mut found = Vec::new();
+    for status in ["UNAVAILABLE", "AVAILABLE"] {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v1/schedule/status?token={}", token))
+            .set_json(serde_json::json!({ "status": status, "schedule_id": schedule.id }))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success(), "status: {}", res.status());
+        // as the server's listener does on every change
+        timetable_cache.reload(&db_pool).await;
+
+        let req = test::TestRequest::get()
+            .uri("/v1/route/search/1/4/02:55")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        found.push(body["paths"].is_array() && path_ids(body).contains(&leg_id));
+    }
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/schedule/{}?token={}", schedule.id, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+
+    // searched while UNAVAILABLE, then AVAILABLE
+    assert_eq!(found, [false, true]);
+}
+
+#[actix_web::test]
+async fn searching_route_with_either_planner_finds_the_same_journey() {
+    let server_config = ServerConfig::new().await;
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let mut quickest = Vec::new();
+    for planner in ["csa", "dijkstra"] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v1/route/search/2/5/08:00?planner={}", planner))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        quickest.push(body["paths"][0].clone());
+    }
+    assert_eq!(quickest[0]["schedule_ids"], serde_json::json!([1, 2]));
+    assert_eq!(quickest[0]["travel_seconds"], 24 * 60);
+    assert_eq!(quickest[0]["transfers"], 0);
+    assert_eq!(
+        quickest[0]["labels"],
+        serde_json::json!(["fastest", "fewest_transfers"])
+    );
+    assert_eq!(quickest[0], quickest[1]);
+
+    for query in ["planner=raptor", "max_transfers=-1"] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v1/route/search/2/5/08:00?{}", query))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_client_error(), "status: {}", res.status());
+    }
+}
+
+#[actix_web::test]
+async fn searching_route_arriving_by_a_time_finds_the_latest_departure() {
+    let server_config = ServerConfig::new().await;
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    // trip 1 leaves place 2 at 08:00 and reaches place 5 at 08:24
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:24?arrive_by=true")
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["paths"][0]["schedule_ids"], serde_json::json!([1, 2]));
+    assert_eq!(body["paths"][0]["travel_seconds"], 24 * 60);
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:23?arrive_by=true")
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(!path_ids(body.clone()).contains(&2), "{}", body);
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:24?arrive_by=true&planner=dijkstra")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn searching_route_late_at_night_returns_next_morning_connections() {
+    let server_config = ServerConfig::new().await;
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/23:30")
+        .to_request();
+    let late: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:00")
+        .to_request();
+    let morning: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    // nothing leaves after 23:30, so the night search waits for the first runs
+    // of the next day and takes longer than the morning one
+    let travel_seconds = |body: &serde_json::Value| body["paths"][0]["travel_seconds"].as_u64();
+    assert!(!path_ids(late.clone()).is_empty());
+    assert!(travel_seconds(&late) > travel_seconds(&morning));
+}
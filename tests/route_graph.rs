@@ -0,0 +1,421 @@
+use chrono::{Duration, NaiveDate, Utc};
+use std::{collections::HashMap, str::FromStr, sync::Arc, thread};
+use wsc2017_tp17::routes::{
+    calendar::{Calendar, CalendarDate},
+    realtime::{LegUpdate, Realtime},
+    route::search::{
+        csa::ConnectionScan,
+        find_shortest_paths,
+        graph::{Graph, GraphLeg},
+        planner::{Journey, JourneyLabel, JourneyPlanner, JourneyQuery},
+        timetable::{Timetable, TimetableLeg},
+    },
+    service_time::ServiceTime,
+    transfer::{LineTransfer, PlaceTransfer, TransferRules},
+    AvailabilityStatus, CalendarException,
+};
+
+fn leg(id: i32, trip_id: i32, places: (i32, i32), times: (&str, &str)) -> GraphLeg {
+    GraphLeg {
+        id,
+        trip_id,
+        line: trip_id,
+        stop_sequence: id,
+        from_place_id: places.0,
+        to_place_id: places.1,
+        departure_time: ServiceTime::from_str(times.0).expect("invalid departure"),
+        arrival_time: ServiceTime::from_str(times.1).expect("invalid arrival"),
+    }
+}
+
+fn query(
+    origin_place_id: i32,
+    destination_place_id: i32,
+    departure_time: ServiceTime,
+) -> JourneyQuery {
+    JourneyQuery {
+        origin_place_id,
+        destination_place_id,
+        time: departure_time,
+        arrive_by: false,
+        transfer_penalty: 0,
+        max_transfers: None,
+    }
+}
+
+fn journey(schedule_ids: Vec<usize>, travel_seconds: usize, transfers: usize) -> Journey {
+    Journey {
+        schedule_ids,
+        travel_seconds,
+        transfers,
+    }
+}
+
+#[test]
+fn graph_follows_trips_without_a_database() {
+    let legs = vec![
+        leg(3, 1, (3, 4), ("08:25", "08:30")),
+        leg(1, 1, (1, 2), ("08:00", "08:10")),
+        leg(2, 1, (2, 3), ("08:12", "08:20")),
+    ];
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+    let graph = Graph::from_legs(legs, departure, &TransferRules::default())
+        .expect("unable to build graph");
+    assert_eq!(graph.nodes.len(), 3);
+
+    let paths = find_shortest_paths(1, 3, departure, &graph).expect("place 3 should be reachable");
+    // the last leg is the one leaving the destination, reached when the leg
+    // before arrives at 08:20
+    assert_eq!(paths[0], (vec![1, 2, 3], 20 * 60));
+}
+
+#[test]
+fn travel_time_is_counted_in_seconds_across_the_hour() {
+    let legs = vec![
+        leg(1, 1, (1, 2), ("08:45", "09:00")),
+        leg(2, 1, (2, 3), ("09:05", "09:10")),
+    ];
+    let departure = ServiceTime::from_str("08:45").expect("invalid departure");
+    let graph = Graph::from_legs(legs, departure, &TransferRules::default())
+        .expect("unable to build graph");
+
+    let paths = find_shortest_paths(1, 2, departure, &graph).expect("place 2 should be reachable");
+    assert_eq!(paths[0], (vec![1, 2], 15 * 60));
+}
+
+#[test]
+fn search_starts_from_the_requested_origin() {
+    let legs = vec![
+        leg(1, 1, (1, 2), ("08:00", "08:10")),
+        leg(2, 2, (3, 2), ("08:05", "08:15")),
+        leg(3, 2, (2, 5), ("08:20", "08:30")),
+    ];
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+    let graph = Graph::from_legs(legs, departure, &TransferRules::default())
+        .expect("unable to build graph");
+
+    let paths = find_shortest_paths(3, 2, departure, &graph).expect("place 2 should be reachable");
+    assert_eq!(paths, [(vec![2, 3], 15 * 60)]);
+    // nothing leaves place 5
+    assert_eq!(find_shortest_paths(5, 2, departure, &graph), None);
+}
+
+#[test]
+fn planners_agree_on_the_same_legs() {
+    let legs = vec![
+        leg(1, 1, (1, 2), ("08:00", "08:10")),
+        leg(2, 1, (2, 3), ("08:12", "08:20")),
+        leg(3, 1, (3, 4), ("08:25", "08:30")),
+        leg(4, 2, (1, 3), ("08:05", "08:30")),
+    ];
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+    let transfers = TransferRules::default();
+    let graph =
+        Graph::from_legs(legs.clone(), departure, &transfers).expect("unable to build graph");
+    let scan = ConnectionScan::new(legs, &transfers);
+
+    let journeys = scan.plan(&query(1, 3, departure));
+    assert_eq!(journeys, Some(vec![journey(vec![1, 2], 20 * 60, 0)]));
+    assert_eq!(graph.plan(&query(1, 3, departure)), journeys);
+
+    // the graph only reaches places that are left again
+    assert_eq!(
+        scan.plan(&query(1, 4, departure)),
+        Some(vec![journey(vec![1, 2, 3], 30 * 60, 0)])
+    );
+    assert_eq!(graph.plan(&query(1, 4, departure)), None);
+    assert_eq!(scan.plan(&query(4, 1, departure)), None);
+}
+
+#[test]
+fn planners_leave_time_to_change_trips() {
+    // trip 2 leaves a minute after trip 1 arrives, trip 3 five minutes after
+    let legs = vec![
+        leg(1, 1, (1, 2), ("08:00", "08:10")),
+        leg(6, 1, (2, 5), ("08:12", "08:20")),
+        leg(2, 2, (2, 3), ("08:11", "08:20")),
+        leg(3, 2, (3, 4), ("08:25", "08:30")),
+        leg(4, 3, (2, 3), ("08:15", "08:30")),
+        leg(5, 3, (3, 4), ("08:35", "08:40")),
+    ];
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+    let place = |min_transfer_seconds| PlaceTransfer {
+        place_id: 2,
+        min_transfer_seconds,
+    };
+    let lines = |min_transfer_seconds| LineTransfer {
+        from_line: 1,
+        to_line: 2,
+        min_transfer_seconds,
+    };
+    let cases = [
+        (TransferRules::new(0, vec![], vec![]), vec![1, 2]),
+        (TransferRules::new(120, vec![], vec![]), vec![1, 4]),
+        (TransferRules::new(120, vec![place(60)], vec![]), vec![1, 2]),
+        (
+            TransferRules::new(0, vec![place(0)], vec![lines(600)]),
+            vec![1, 4],
+        ),
+    ];
+
+    for (transfers, ridden) in cases {
+        let graph =
+            Graph::from_legs(legs.clone(), departure, &transfers).expect("unable to build graph");
+        let scan = ConnectionScan::new(legs.clone(), &transfers);
+
+        let journeys = scan
+            .plan(&query(1, 3, departure))
+            .expect("place 3 should be reachable");
+        assert_eq!(journeys[0].schedule_ids, ridden, "{:?}", transfers);
+        let paths = graph
+            .plan(&query(1, 3, departure))
+            .expect("place 3 should be reachable");
+        assert_eq!(paths[0], journeys[0], "{:?}", transfers);
+    }
+}
+
+#[test]
+fn transfer_penalty_ranks_direct_trips_first() {
+    // trip 1 goes straight to place 3, trips 2 and 3 are faster with a change
+    let legs = vec![
+        leg(1, 1, (1, 3), ("08:00", "09:00")),
+        leg(2, 1, (3, 4), ("09:05", "09:10")),
+        leg(3, 2, (1, 2), ("08:00", "08:10")),
+        leg(6, 2, (2, 5), ("08:12", "08:20")),
+        leg(4, 3, (2, 3), ("08:15", "08:30")),
+        leg(5, 3, (3, 4), ("08:35", "08:40")),
+    ];
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+    let transfers = TransferRules::default();
+    let graph =
+        Graph::from_legs(legs.clone(), departure, &transfers).expect("unable to build graph");
+    let scan = ConnectionScan::new(legs, &transfers);
+
+    for (transfer_penalty, quickest) in [
+        (0, journey(vec![3, 4], 30 * 60, 1)),
+        (3600, journey(vec![1], 60 * 60, 0)),
+    ] {
+        let query = JourneyQuery {
+            transfer_penalty,
+            ..query(1, 3, departure)
+        };
+        let journeys = scan.plan(&query).expect("place 3 should be reachable");
+        assert_eq!(journeys[0], quickest);
+        assert_eq!(graph.plan(&query), Some(journeys));
+    }
+}
+
+#[test]
+fn planners_trade_arrival_time_for_transfers() {
+    // trip 1 goes straight to place 3, trips 2 and 3 are faster with a change,
+    // trip 4 is as direct as trip 1 but slower
+    let legs = vec![
+        leg(1, 1, (1, 3), ("08:00", "09:00")),
+        leg(2, 1, (3, 4), ("09:05", "09:10")),
+        leg(3, 2, (1, 2), ("08:00", "08:10")),
+        leg(6, 2, (2, 5), ("08:12", "08:20")),
+        leg(4, 3, (2, 3), ("08:15", "08:30")),
+        leg(5, 3, (3, 4), ("08:35", "08:40")),
+        leg(7, 4, (1, 3), ("08:05", "09:10")),
+        leg(8, 4, (3, 4), ("09:15", "09:20")),
+    ];
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+    let transfers = TransferRules::default();
+    let graph =
+        Graph::from_legs(legs.clone(), departure, &transfers).expect("unable to build graph");
+    let scan = ConnectionScan::new(legs, &transfers);
+
+    let fastest = journey(vec![3, 4], 30 * 60, 1);
+    let direct = journey(vec![1], 60 * 60, 0);
+    let journeys = scan
+        .plan(&query(1, 3, departure))
+        .expect("place 3 should be reachable");
+    assert_eq!(journeys, [fastest.clone(), direct.clone()]);
+    assert_eq!(graph.plan(&query(1, 3, departure)), Some(journeys.clone()));
+    assert_eq!(fastest.labels(&journeys), [JourneyLabel::Fastest]);
+    assert_eq!(direct.labels(&journeys), [JourneyLabel::FewestTransfers]);
+
+    let query = JourneyQuery {
+        max_transfers: Some(0),
+        ..query(1, 3, departure)
+    };
+    assert_eq!(scan.plan(&query), Some(vec![direct.clone()]));
+    assert_eq!(graph.plan(&query), Some(vec![direct]));
+}
+
+#[test]
+fn arriving_by_a_time_finds_the_latest_departures() {
+    // trip 1 goes to place 3 without a change, trips 2 and 3 leave later with
+    // one, trip 4 arrives too late
+    let legs = vec![
+        leg(1, 1, (1, 4), ("08:00", "08:20")),
+        leg(7, 1, (4, 3), ("08:25", "08:40")),
+        leg(2, 2, (1, 2), ("08:20", "08:30")),
+        leg(3, 3, (2, 3), ("08:35", "08:50")),
+        leg(4, 4, (1, 3), ("08:30", "09:10")),
+    ];
+    let transfers = TransferRules::default();
+    let graph = Graph::from_legs(
+        legs.clone(),
+        ServiceTime::from_str("08:00").expect("invalid departure"),
+        &transfers,
+    )
+    .expect("unable to build graph");
+    let scan = ConnectionScan::new(legs, &transfers);
+    let arrive_by = |time| JourneyQuery {
+        arrive_by: true,
+        ..query(1, 3, ServiceTime::from_str(time).expect("invalid arrival"))
+    };
+
+    let direct = journey(vec![1, 7], 60 * 60, 0);
+    assert_eq!(
+        scan.plan(&arrive_by("09:00")),
+        Some(vec![journey(vec![2, 3], 40 * 60, 1), direct.clone()])
+    );
+    let query = JourneyQuery {
+        max_transfers: Some(0),
+        ..arrive_by("09:00")
+    };
+    assert_eq!(scan.plan(&query), Some(vec![direct]));
+    assert_eq!(
+        scan.plan(&arrive_by("08:45")),
+        Some(vec![journey(vec![1, 7], 45 * 60, 0)])
+    );
+    assert_eq!(scan.plan(&arrive_by("08:30")), None);
+    assert_eq!(graph.plan(&arrive_by("09:00")), None);
+}
+
+#[test]
+fn graph_can_be_searched_from_many_threads() {
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+    let graph = Arc::new(
+        Graph::from_legs(
+            vec![
+                leg(1, 1, (1, 2), ("08:00", "08:10")),
+                leg(2, 1, (2, 3), ("08:12", "08:20")),
+            ],
+            departure,
+            &TransferRules::default(),
+        )
+        .expect("unable to build graph"),
+    );
+
+    let searches: Vec<_> = (0..4)
+        .map(|_| {
+            let graph = Arc::clone(&graph);
+            thread::spawn(move || find_shortest_paths(1, 2, departure, &graph))
+        })
+        .collect();
+    for search in searches {
+        let paths = search
+            .join()
+            .expect("search panicked")
+            .expect("place 2 should be reachable");
+        assert_eq!(paths[0].0, [1, 2]);
+    }
+}
+
+#[test]
+fn cached_timetable_leaves_out_trips_not_running_that_day() {
+    let date = NaiveDate::from_ymd_opt(2024, 6, 19).expect("invalid date");
+    let timetable_leg = |id, trip_id| {
+        let leg = leg(id, trip_id, (1, 2), ("08:00", "08:10"));
+        TimetableLeg {
+            id,
+            trip_id,
+            line: trip_id,
+            stop_sequence: 1,
+            from_place_id: leg.from_place_id,
+            to_place_id: leg.to_place_id,
+            vehicle_type: None,
+            status: Some(AvailabilityStatus::AVAILABLE),
+            departure_time: leg.departure_time,
+            arrival_time: leg.arrival_time,
+        }
+    };
+    let calendar = Calendar {
+        id: 1,
+        name: "daily but the 19th".to_owned(),
+        monday: true,
+        tuesday: true,
+        wednesday: true,
+        thursday: true,
+        friday: true,
+        saturday: true,
+        sunday: true,
+        start_date: NaiveDate::from_ymd_opt(2024, 1, 1).expect("invalid date"),
+        end_date: NaiveDate::from_ymd_opt(2024, 12, 31).expect("invalid date"),
+        dates: vec![CalendarDate {
+            date,
+            exception: CalendarException::REMOVED,
+        }],
+    };
+    let timetable = Timetable::new(
+        vec![timetable_leg(2, 2), timetable_leg(1, 1)],
+        vec![calendar],
+        HashMap::from([(2, 1)]),
+    );
+    let departure = ServiceTime::from_str("07:00").expect("invalid departure");
+
+    let graph = Graph::new(&timetable, departure, date, None, &Realtime::default())
+        .expect("unable to build graph");
+    assert_eq!(
+        graph.nodes.iter().map(|node| node.id).collect::<Vec<_>>(),
+        [1]
+    );
+
+    let next_day = date.succ_opt().expect("invalid date");
+    let graph = Graph::new(&timetable, departure, next_day, None, &Realtime::default())
+        .expect("unable to build graph");
+    assert_eq!(graph.nodes.len(), 2);
+}
+
+#[test]
+fn legs_predicted_to_arrive_before_they_leave_are_left_out() {
+    let date = NaiveDate::from_ymd_opt(2024, 6, 19).expect("invalid date");
+    let timetable_leg = |id, places, times| {
+        let leg = leg(id, 1, places, times);
+        TimetableLeg {
+            id,
+            trip_id: leg.trip_id,
+            line: leg.line,
+            stop_sequence: leg.stop_sequence,
+            from_place_id: leg.from_place_id,
+            to_place_id: leg.to_place_id,
+            vehicle_type: None,
+            status: Some(AvailabilityStatus::AVAILABLE),
+            departure_time: leg.departure_time,
+            arrival_time: leg.arrival_time,
+        }
+    };
+    let timetable = Timetable::new(
+        vec![
+            timetable_leg(1, (1, 2), ("08:00", "08:10")),
+            timetable_leg(2, (2, 3), ("08:12", "08:20")),
+        ],
+        Vec::new(),
+        HashMap::new(),
+    );
+    // an early arrival given without moving the departure
+    let realtime = Realtime {
+        legs: HashMap::from([(
+            (1, date),
+            LegUpdate {
+                schedule_id: 1,
+                service_date: date,
+                departure_delay: 0,
+                arrival_delay: -15 * 60,
+                cancelled: false,
+                expires_at: Utc::now() + Duration::minutes(10),
+            },
+        )]),
+    };
+    let departure = ServiceTime::from_str("08:00").expect("invalid departure");
+
+    let graph = Graph::new(&timetable, departure, date, None, &realtime)
+        .expect("a leg arriving early should not fail the graph");
+    assert_eq!(
+        graph.nodes.iter().map(|node| node.id).collect::<Vec<_>>(),
+        [2]
+    );
+}
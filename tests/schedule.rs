@@ -0,0 +1,352 @@
+mod auth;
+
+use actix_web::{http::header::ContentType, test};
+use auth::{get_admin_session_token, get_user_session_token};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    gtfs::Severity,
+    routes::{schedule::Schedule, AvailabilityStatus, Vehicle},
+    validate::{CheckKind, ValidationReport},
+};
+
+#[actix_web::test]
+async fn schedules_support_crud_for_admins() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/schedule?token={}", token))
+        .insert_header(ContentType::json())
+        .set_payload(
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 2, "type": "BUS",
+                "departure_time": "10:00:00", "arrival_time": "10:10:00",
+                "distance": 5000, "speed": 30}"#,
+        )
+        .to_request();
+    let schedule: Schedule = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(schedule.vehicle_type, Some(Vehicle::BUS));
+    assert_eq!(schedule.status, Some(AvailabilityStatus::AVAILABLE));
+    assert_eq!(schedule.stop_sequence, 1);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/schedule?token={}", token))
+        .set_json(serde_json::json!({
+            "line": 99, "from_place_id": 2, "to_place_id": 3, "trip_id": schedule.trip_id,
+            "departure_time": "10:12:00", "arrival_time": "10:20:00"
+        }))
+        .to_request();
+    let next: Schedule = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(next.trip_id, schedule.trip_id);
+    assert_eq!(next.stop_sequence, 2);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/v1/schedule?token={}", token))
+        .set_json(serde_json::json!({
+            "line": 99, "from_place_id": 2, "to_place_id": 3, "trip_id": schedule.trip_id,
+            "stop_sequence": 2, "departure_time": "10:12:00", "arrival_time": "10:20:00"
+        }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 409);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/schedule/{}?token={}", schedule.id, token))
+        .insert_header(ContentType::json())
+        .set_payload(
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 2, "type": "TRAIN",
+                "departure_time": "10:00:00", "arrival_time": "10:20:00",
+                "status": "UNAVAILABLE"}"#,
+        )
+        .to_request();
+    let updated: Schedule = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.id, schedule.id);
+    assert_eq!(updated.vehicle_type, Some(Vehicle::TRAIN));
+    assert_eq!(updated.status, Some(AvailabilityStatus::UNAVAILABLE));
+
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/v1/schedule?token={}&line=99&place_id=2&from_time=09:59&to_time=10:01",
+            token
+        ))
+        .to_request();
+    let schedules: Vec<Schedule> = test::call_and_read_body_json(&app, req).await;
+    assert!(schedules.contains(&updated));
+    assert!(schedules.iter().all(|s| s.line == 99));
+
+    for id in [schedule.id, next.id] {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v1/schedule/{}?token={}", id, token))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success(), "status: {}", res.status());
+    }
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/schedule/{}?token={}", schedule.id, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 404);
+}
+
+#[actix_web::test]
+async fn creating_schedule_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let valid = r#"{"line": 99, "from_place_id": 1, "to_place_id": 2,
+        "departure_time": "10:00:00", "arrival_time": "10:10:00"}"#;
+    let test_cases = [
+        (user_token.as_str(), valid, "user token"),
+        (
+            admin_token.as_str(),
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 999999,
+                "departure_time": "10:00:00", "arrival_time": "10:10:00"}"#,
+            "unknown place",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 2,
+                "departure_time": "10:00:00", "arrival_time": "09:50:00"}"#,
+            "arrival before departure",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 2, "type": "BOAT",
+                "departure_time": "10:00:00", "arrival_time": "10:10:00"}"#,
+            "invalid vehicle type",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 2, "trip_id": 999999,
+                "departure_time": "10:00:00", "arrival_time": "10:10:00"}"#,
+            "unknown trip",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 2, "trip_id": 1,
+                "departure_time": "10:00:00", "arrival_time": "10:10:00"}"#,
+            "trip on another line",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"line": 99, "from_place_id": 1, "to_place_id": 2, "status": "MAYBE",
+                "departure_time": "10:00:00", "arrival_time": "10:10:00"}"#,
+            "invalid status",
+        ),
+    ];
+
+    for (token, payload, msg) in test_cases {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v1/schedule?token={}", token))
+            .insert_header(ContentType::json())
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
+
+#[actix_web::test]
+async fn updating_line_status_updates_every_leg() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool).await;
+    for status in [
+        AvailabilityStatus::UNAVAILABLE,
+        AvailabilityStatus::AVAILABLE,
+    ] {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v1/schedule/status?token={}", token))
+            .set_json(serde_json::json!({
+                "status": status,
+                "line": 2,
+                "from_time": "08:00:00",
+                "to_time": "09:00:00"
+            }))
+            .to_request();
+        let schedules: Vec<Schedule> = test::call_and_read_body_json(&app, req).await;
+        assert!(!schedules.is_empty());
+        for schedule in schedules {
+            assert_eq!(schedule.line, 2);
+            assert_eq!(schedule.status, Some(status));
+            assert!(schedule
+                .departure_time
+                .is_some_and(|time| time.to_string().as_str() <= "09:00:00"));
+        }
+    }
+}
+
+#[actix_web::test]
+async fn updating_status_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let test_cases = [
+        (
+            user_token.as_str(),
+            r#"{"status": "UNAVAILABLE", "line": 1}"#,
+            "user token",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"status": "UNAVAILABLE"}"#,
+            "no selector",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"status": "MAYBE", "line": 1}"#,
+            "invalid status",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"status": "UNAVAILABLE", "from_time": "10:00:00", "to_time": "09:00:00"}"#,
+            "inverted time window",
+        ),
+        (
+            admin_token.as_str(),
+            r#"{"status": "UNAVAILABLE", "schedule_id": 999999}"#,
+            "unknown schedule",
+        ),
+    ];
+
+    for (token, payload, msg) in test_cases {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v1/schedule/status?token={}", token))
+            .insert_header(ContentType::json())
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error from {}, got {} instead",
+            msg,
+            res.status()
+        );
+    }
+}
+
+#[actix_web::test]
+async fn validation_reports_broken_legs() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool.clone()).await;
+    let uri = format!("/v1/schedule/validation?token={}", token);
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    let report: ValidationReport = test::call_and_read_body_json(&app, req).await;
+    assert!(
+        !report.has_errors(),
+        "seed has errors: {:?}",
+        report.findings
+    );
+
+    // trips of a calendar that never runs, so searches do not see them
+    let calendar_id = sqlx::query_scalar!(
+        "INSERT INTO calendars (name, monday, tuesday, wednesday, thursday, friday, saturday, sunday,
+         start_date, end_date)
+         VALUES ('never', false, false, false, false, false, false, false, '2024-01-01', '2024-01-01')
+         RETURNING id"
+    )
+    .fetch_one(&db_pool)
+    .await
+    .expect("unable to insert calendar");
+    sqlx::query!("INSERT INTO lines (id, short_name, type) VALUES (9041, 'V', 'BUS')")
+        .execute(&db_pool)
+        .await
+        .expect("unable to insert line");
+    let trip_ids = sqlx::query_scalar!(
+        "INSERT INTO trips (line, calendar_id) VALUES (9041, $1), (9041, $1) RETURNING id",
+        calendar_id
+    )
+    .fetch_all(&db_pool)
+    .await
+    .expect("unable to insert trips");
+    let leg_ids = sqlx::query_scalar!(
+        "INSERT INTO schedules (line, trip_id, stop_sequence, from_place_id, to_place_id,
+         departure_time, arrival_time, distance, speed)
+         VALUES (9041, $1, 1, 2, 3, 7200, 7800, 5000, 30),
+         (9041, $1, 2, 5, 15, 7700, 7760, 50000, 30),
+         (9041, $1, 3, 15, 999999, 7800, 8400, NULL, NULL),
+         (9041, $2, 1, 2, 3, 7200, 7800, 5000, 30)
+         RETURNING id",
+        trip_ids[0],
+        trip_ids[1]
+    )
+    .fetch_all(&db_pool)
+    .await
+    .expect("unable to insert legs");
+
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    let report: ValidationReport = test::call_and_read_body_json(&app, req).await;
+
+    sqlx::query!("DELETE FROM trips WHERE line = 9041")
+        .execute(&db_pool)
+        .await
+        .expect("unable to delete trips");
+    sqlx::query!("DELETE FROM lines WHERE id = 9041")
+        .execute(&db_pool)
+        .await
+        .expect("unable to delete line");
+    sqlx::query!("DELETE FROM calendars WHERE id = $1", calendar_id)
+        .execute(&db_pool)
+        .await
+        .expect("unable to delete calendar");
+
+    let found = |kind: CheckKind, schedule_ids: &[i32]| {
+        report
+            .findings
+            .iter()
+            .any(|finding| finding.kind == kind && finding.schedule_ids == schedule_ids)
+    };
+    assert!(found(CheckKind::BrokenChain, &[leg_ids[1]]));
+    assert!(found(CheckKind::NonMonotonicTime, &[leg_ids[1]]));
+    assert!(found(CheckKind::ImplausibleSpeed, &[leg_ids[1]]));
+    assert!(found(CheckKind::UnknownPlace, &[leg_ids[2]]));
+    assert!(found(CheckKind::DuplicateLeg, &[leg_ids[0], leg_ids[3]]));
+    assert!(report.has_errors());
+    assert_eq!(
+        report.errors,
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .count()
+    );
+
+    let token = get_user_session_token(db_pool).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/schedule/validation?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(
+        res.status().is_client_error(),
+        "expecting client error for user token but got {}",
+        res.status()
+    );
+}
@@ -0,0 +1,136 @@
+mod auth;
+
+use actix_web::{http::header::ContentType, test};
+use auth::{get_admin_session_token, get_user_session_token};
+use wsc2017_tp17::{
+    config::ServerConfig,
+    routes::transfer::{LineTransfer, PlaceTransfer, Transfers},
+};
+
+#[actix_web::test]
+async fn transfer_rules_are_listed_as_search_uses_them() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let timetable_cache = server_config.timetable_cache.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let token = get_admin_session_token(db_pool.clone()).await;
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/transfer/place/17?token={}", token))
+        .set_json(serde_json::json!({ "min_transfer_seconds": 300 }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/transfer/line/2/3?token={}", token))
+        .set_json(serde_json::json!({ "min_transfer_seconds": 600 }))
+        .to_request();
+    let rule: LineTransfer = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(rule.min_transfer_seconds, 600);
+    // as the server's listener does on every change
+    timetable_cache.reload(&db_pool).await;
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/transfer?token={}", token))
+        .to_request();
+    let transfers: Transfers = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/v1/transfer/place/17?token={}", token))
+        .set_json(serde_json::json!({ "min_transfer_seconds": null }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/transfer/line/2/3?token={}", token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.status().is_success(), "status: {}", res.status());
+    timetable_cache.reload(&db_pool).await;
+
+    assert!(transfers.places.contains(&PlaceTransfer {
+        place_id: 17,
+        min_transfer_seconds: 300
+    }));
+    assert!(transfers.lines.contains(&rule));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/v1/transfer?token={}", token))
+        .to_request();
+    let transfers: Transfers = test::call_and_read_body_json(&app, req).await;
+    assert!(transfers.places.iter().all(|rule| rule.place_id != 17));
+    assert!(!transfers.lines.contains(&rule));
+}
+
+#[actix_web::test]
+async fn transfer_returns_client_error_for_invalid_requests() {
+    let server_config = ServerConfig::new().await;
+    let db_pool = server_config.db_pool.pool.clone();
+    let app =
+        test::init_service(actix_web::App::new().configure(move |cfg| server_config.config(cfg)))
+            .await;
+
+    let admin_token = get_admin_session_token(db_pool.clone()).await;
+    let user_token = get_user_session_token(db_pool).await;
+    let cases = [
+        (
+            user_token.as_str(),
+            "/v1/transfer/place/17",
+            r#"{"min_transfer_seconds": 60}"#,
+            "user token",
+        ),
+        (
+            admin_token.as_str(),
+            "/v1/transfer/place/17",
+            r#"{"min_transfer_seconds": -1}"#,
+            "negative time",
+        ),
+        (
+            admin_token.as_str(),
+            "/v1/transfer/place/999999",
+            r#"{"min_transfer_seconds": 60}"#,
+            "unknown place",
+        ),
+        (
+            admin_token.as_str(),
+            "/v1/transfer/line/2/3",
+            r#"{"min_transfer_seconds": null}"#,
+            "no time",
+        ),
+        (
+            admin_token.as_str(),
+            "/v1/transfer/line/2/999999",
+            r#"{"min_transfer_seconds": 60}"#,
+            "unknown line",
+        ),
+    ];
+
+    for (token, uri, payload, msg) in cases {
+        let req = test::TestRequest::put()
+            .uri(&format!("{}?token={}", uri, token))
+            .insert_header(ContentType::json())
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(
+            res.status().is_client_error(),
+            "expecting client error for {} but got {}",
+            msg,
+            res.status()
+        );
+    }
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/v1/transfer/line/3/2?token={}", admin_token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 404);
+
+    let req = test::TestRequest::get()
+        .uri("/v1/route/search/2/5/08:00?transfer_penalty=-60")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status().as_u16(), 400);
+}